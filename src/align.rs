@@ -1,8 +1,7 @@
 //! Padding utilities
 
-use crate::formatter::get_length;
 use std::fmt;
-use std::fmt::Write;
+use unicode_width::UnicodeWidthChar;
 
 pub struct PaddingEntry {
     pub text: String,
@@ -16,60 +15,233 @@ pub enum Alignment {
     Right,
 }
 
+/// Pads the entries into their columns and calls func with every line.
+///
+/// If the line is wider than the max width the last entry gets wrapped at spaces, `::` and `/`.
+/// The wrapped lines are indented to start under the last entry and continue the colors of the line above.
 pub fn align(
-    entries: &Vec<PaddingEntry>,
+    entries: &[PaddingEntry],
     max_width: usize,
     pad_char: char,
     mut func: impl FnMut(String) -> fmt::Result,
 ) -> fmt::Result {
+    let Some((text, columns)) = entries.split_last() else {
+        return func(String::new());
+    };
+
     let mut offset = 0;
-    let mut line = String::new();
-    for entry in entries {
-        let text = &entry.text;
-        let length = get_length(text);
-        if length > entry.width {
-            offset += length - entry.width;
-            line.push_str(text);
+    let mut prefix = String::new();
+    for entry in columns {
+        pad(&mut prefix, entry, &mut offset, pad_char);
+    }
+
+    let mut text_line = String::new();
+    pad(&mut text_line, text, &mut offset, pad_char);
+
+    if display_width(&prefix) + display_width(&text_line) <= max_width {
+        return func(prefix + &text_line);
+    }
+
+    // Continuation lines start where the text starts, including its own indentation.
+    let leading = text_line.chars().take_while(|ch| *ch == ' ').count();
+    let indent = (display_width(&prefix) + leading).min(max_width / 2);
+
+    let mut wrapper = Wrapper {
+        max_width,
+        indent,
+        lines: Vec::new(),
+        line: String::new(),
+        width: 0,
+        escapes: Vec::new(),
+    };
+    wrapper.push_word(&atoms(&prefix).collect::<Vec<_>>());
+    for word in words(&text_line) {
+        wrapper.push_word(&word);
+    }
+
+    for line in wrapper.finish() {
+        func(line)?;
+    }
+
+    Ok(())
+}
+
+fn pad(line: &mut String, entry: &PaddingEntry, offset: &mut usize, pad_char: char) {
+    let text = &entry.text;
+    let length = display_width(text);
+    if length > entry.width {
+        *offset += length - entry.width;
+        line.push_str(text);
+    } else {
+        let mut padding_size = entry.width - length;
+
+        // try to correct the offset
+        let offset_amount = (*offset).clamp(0, padding_size);
+        *offset -= offset_amount;
+        padding_size -= offset_amount;
+
+        let padding = pad_char.to_string().repeat(padding_size);
+        match entry.alignment {
+            Alignment::Left => {
+                line.push_str(text);
+                line.push_str(&padding);
+            }
+            Alignment::Center => {
+                let (left_padding, right_padding) = padding.split_at(padding.len() / 2);
+                line.push_str(left_padding);
+                line.push_str(text);
+                line.push_str(right_padding);
+            }
+            Alignment::Right => {
+                line.push_str(&padding);
+                line.push_str(text);
+            }
+        }
+    }
+}
+
+/// A piece of text which cannot be split.
+#[derive(Clone, Copy)]
+pub(crate) enum Atom<'a> {
+    /// A terminal escape sequence, these take up no space.
+    Escape(&'a str),
+    Char(char),
+}
+
+impl Atom<'_> {
+    fn width(&self) -> usize {
+        match self {
+            Atom::Escape(_) => 0,
+            Atom::Char(ch) => ch.width().unwrap_or(0),
+        }
+    }
+}
+
+/// Splits text into characters and escape sequences.
+pub(crate) fn atoms(text: &str) -> impl Iterator<Item = Atom<'_>> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        let mut chars = rest.char_indices();
+        let (_, ch) = chars.next()?;
+        let end = if ch == '\x1b' {
+            match chars.next() {
+                // CSI sequences end with a byte in the range @ to ~
+                Some((_, '[')) => chars
+                    .find(|(_, ch)| ('@'..='~').contains(ch))
+                    .map(|(i, ch)| i + ch.len_utf8())
+                    .unwrap_or(rest.len()),
+                Some((i, ch)) => i + ch.len_utf8(),
+                None => rest.len(),
+            }
         } else {
-            let mut padding_size = entry.width - length;
+            ch.len_utf8()
+        };
 
-            // try to correct the offset
-            let offset_amount = offset.clamp(0, padding_size);
-            offset -= offset_amount;
-            padding_size -= offset_amount;
+        let (atom, remaining) = rest.split_at(end);
+        rest = remaining;
+        Some(if ch == '\x1b' {
+            Atom::Escape(atom)
+        } else {
+            Atom::Char(ch)
+        })
+    })
+}
 
-            let padding = pad_char.to_string().repeat(padding_size);
-            match entry.alignment {
-                Alignment::Left => {
-                    write!(&mut line, "{text}{padding}")?;
-                }
-                Alignment::Center => {
-                    let (left_padding, right_padding) = padding.split_at(padding.len() / 2);
-                    write!(&mut line, "{left_padding}{text}{right_padding}")?;
-                }
-                Alignment::Right => {
-                    write!(&mut line, "{padding}{text}")?;
-                }
+/// The amount of terminal columns the text takes up, escape sequences are skipped.
+pub(crate) fn display_width(text: &str) -> usize {
+    atoms(text).map(|atom| atom.width()).sum()
+}
+
+/// Splits the text into words which end after spaces, `::` or `/`.
+fn words(text: &str) -> Vec<Vec<Atom<'_>>> {
+    let mut words = Vec::new();
+    let mut word: Vec<Atom> = Vec::new();
+    let mut previous = None;
+    for atom in atoms(text) {
+        if let Atom::Char(ch) = atom {
+            let boundary = match previous {
+                Some(' ') => ch != ' ',
+                Some('/') => true,
+                Some(':') => matches!(word.iter().rev().nth(1), Some(Atom::Char(':'))) && ch != ':',
+                _ => false,
+            };
+            if boundary {
+                words.push(std::mem::take(&mut word));
             }
+            previous = Some(ch);
         }
+        word.push(atom);
     }
 
-    if get_length(&line) > max_width {
-        let mut line_builder = String::new();
-        for ch in line.chars() {
-            line_builder.push(ch);
-            if get_length(&line_builder) >= max_width {
-                func(line_builder)?;
-                line_builder = String::new();
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+struct Wrapper<'a> {
+    max_width: usize,
+    indent: usize,
+    lines: Vec<String>,
+    line: String,
+    width: usize,
+    /// Every escape sequence since the last reset, these get replayed on the next line to continue the colors.
+    escapes: Vec<&'a str>,
+}
+
+impl<'a> Wrapper<'a> {
+    fn push_word(&mut self, word: &[Atom<'a>]) {
+        // Trailing spaces may hang over the end of the line as they get dropped.
+        let trailing = word
+            .iter()
+            .rev()
+            .take_while(|atom| matches!(atom, Atom::Char(' ') | Atom::Escape(_)))
+            .map(|atom| atom.width())
+            .sum::<usize>();
+        let width: usize = word.iter().map(|atom| atom.width()).sum();
+        if self.width > self.indent && self.width + width - trailing > self.max_width {
+            self.break_line();
+        }
+
+        for atom in word {
+            match *atom {
+                Atom::Escape(escape) => {
+                    if escape == "\x1b[0m" || escape == "\x1b[m" {
+                        self.escapes.clear();
+                    } else {
+                        self.escapes.push(escape);
+                    }
+                    self.line.push_str(escape);
+                }
+                Atom::Char(ch) => {
+                    let width = atom.width();
+                    if self.width + width > self.max_width {
+                        if ch == ' ' {
+                            continue;
+                        }
+                        // The word does not fit on a line of its own.
+                        self.break_line();
+                    }
+                    self.line.push(ch);
+                    self.width += width;
+                }
             }
         }
+    }
 
-        if !line_builder.is_empty() {
-            func(line_builder)?;
+    fn break_line(&mut self) {
+        if !self.escapes.is_empty() {
+            self.line.push_str("\x1b[0m");
         }
-    } else {
-        func(line)?;
+
+        let indent = " ".repeat(self.indent);
+        let line = std::mem::replace(&mut self.line, indent + &self.escapes.concat());
+        self.lines.push(line);
+        self.width = self.indent;
     }
 
-    Ok(())
+    fn finish(mut self) -> Vec<String> {
+        self.lines.push(self.line);
+        self.lines
+    }
 }
@@ -1,16 +1,32 @@
-use crate::ext::get_caller;
+use crate::std_error::AuditStdError;
+use crate::style::{SpanStyle, StyledText};
 use crate::{get_audit_formatter, get_audit_processor};
-use backtrace::{Backtrace, Frame};
+use backtrace::Backtrace;
 use owo_colors::{AnsiColors, DynColors};
+use std::borrow::Cow;
 use std::error::Error;
 use std::fmt::{Debug, Display, Formatter, Write};
 use std::ops::{Deref, DerefMut};
+use std::panic::Location;
 
 /// An Audit is the Error type of Anyways. It allows you to hold any type of error dynamically without worrying about it.
 pub struct Audit {
     pub backtrace: Backtrace,
     pub errors: Vec<AuditError>,
     pub custom_sections: Vec<AuditSection>,
+    extension: Option<Box<AuditExtension>>,
+}
+
+/// The rarely used parts of an audit.
+/// They are only allocated once one of them gets set, to keep `Result<T, Audit>` small.
+#[derive(Default)]
+struct AuditExtension {
+    attachments: Vec<AuditAttachment>,
+    children: Vec<Audit>,
+    exit_code: Option<u8>,
+    code: Option<String>,
+    #[cfg(feature = "tracing")]
+    span_trace: Option<tracing_error::SpanTrace>,
 }
 
 impl Audit {
@@ -19,43 +35,261 @@ impl Audit {
             backtrace: Backtrace::new_unresolved(),
             errors: vec![],
             custom_sections: vec![],
+            extension: Self::capture_extension(),
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    fn capture_extension() -> Option<Box<AuditExtension>> {
+        let span_trace = tracing_error::SpanTrace::capture();
+        (span_trace.status() == tracing_error::SpanTraceStatus::CAPTURED).then(|| {
+            Box::new(AuditExtension {
+                span_trace: Some(span_trace),
+                ..Default::default()
+            })
+        })
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    fn capture_extension() -> Option<Box<AuditExtension>> {
+        None
+    }
+
+    fn extension_mut(&mut self) -> &mut AuditExtension {
+        self.extension.get_or_insert_with(Default::default)
+    }
+
+    /// Independent failures reported as part of this audit, each with its own errors and backtrace.
+    pub fn children(&self) -> &[Audit] {
+        self.extension.as_ref().map_or(&[], |extension| &extension.children)
+    }
+
+    /// Notes, help and warnings which get shown after the errors.
+    pub fn attachments(&self) -> &[AuditAttachment] {
+        self.extension.as_ref().map_or(&[], |extension| &extension.attachments)
+    }
+
+    /// The exit code set for this audit, see [get_exit_code](Audit::get_exit_code) for the code the process exits with.
+    pub fn exit_code(&self) -> Option<u8> {
+        self.extension.as_ref().and_then(|extension| extension.exit_code)
+    }
+
+    /// Sets the exit code of the process if it fails with this audit, see the [exit](crate::exit) module.
+    pub fn set_exit_code(&mut self, code: u8) -> &mut Self {
+        self.extension_mut().exit_code = Some(code);
+        self
+    }
+
+    /// The error code set on the audit itself, used for its outermost error if that has no code of its own.
+    pub fn code(&self) -> Option<&str> {
+        self.extension.as_ref().and_then(|extension| extension.code.as_deref())
+    }
+
+    /// Sets the error code of the audit itself, see the [code](crate::code) module.
+    pub fn set_code(&mut self, code: impl Into<String>) -> &mut Self {
+        self.extension_mut().code = Some(code.into());
+        self
+    }
+
+    /// The code shown for the error at `index`, the outermost error falls back to the [audit code](Audit::code).
+    pub(crate) fn error_code(&self, index: usize) -> Option<&str> {
+        let code = self.errors.get(index)?.code.as_deref();
+        if index == 0 {
+            code.or_else(|| self.code())
+        } else {
+            code
         }
     }
 
+    /// The tracing spans which were entered when the audit got created, None if no span was entered.
+    #[cfg(feature = "tracing")]
+    pub fn span_trace(&self) -> Option<&tracing_error::SpanTrace> {
+        self.extension.as_ref().and_then(|extension| extension.span_trace.as_ref())
+    }
+
+    /// Creates an audit holding the error.
+    /// If the error is an [AuditStdError] the audit it was created from gets returned instead.
+    #[track_caller]
     pub fn new(err: impl Into<AuditError>) -> Audit {
+        let mut err = err.into();
+        match AuditStdError::recover(err.error) {
+            Ok(error) => return error.into_audit(),
+            Err(error) => err.error = error,
+        }
+
         let mut audit = Audit::new_empty();
         audit.push_err(err);
         audit
     }
 
+    /// Joins multiple audits into one audit which reports each of them as a child.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anyways::audit::Audit;
+    ///
+    /// let audits = ["name", "version"].map(|field| Audit::new(format!("Missing field {field}")));
+    /// let audit = Audit::join(audits);
+    /// assert_eq!(audit.children().len(), 2);
+    /// ```
+    pub fn join(audits: impl IntoIterator<Item = Audit>) -> Audit {
+        let mut audit = Audit::new_empty();
+        audit.extension_mut().children.extend(audits);
+        audit
+    }
+
+    /// Appends a child audit which gets reported under the errors of this audit.
+    pub fn push_child(&mut self, child: Audit) -> &mut Self {
+        self.extension_mut().children.push(child);
+        self
+    }
+
+    /// Downcasts one of the audit errors to a concrete error type.
+    /// Only the errors of the audit and its children are checked as the errors exposed through [Error::source] cannot be borrowed mutably.
     pub fn downcast_mut<T: Err>(&mut self) -> Option<&mut T> {
-        for err in &mut self.errors {
-            if let Some(err) = err.downcast_mut::<T>() {
-                return Some(err);
-            }
+        if let Some(pos) = self.errors.iter().position(|err| err.is::<T>()) {
+            return self.errors[pos].downcast_mut::<T>();
         }
 
-        None
+        self.extension
+            .as_mut()?
+            .children
+            .iter_mut()
+            .find_map(|child| child.downcast_mut::<T>())
     }
 
-
     /// Downcasts the audit to a concrete error type by checking the chain of errors and attempting to downcast them.
     /// If it cannot find any error that matches T it will return None, else it will return Some(value)
     pub fn downcast_ref<T: Err>(&self) -> Option<&T> {
-        for err in &self.errors {
-            if let Some(err) = err.downcast_ref::<T>() {
-                return Some(err);
+        self.chain().find_map(|err| err.downcast_ref::<T>())
+    }
+
+    /// Takes ownership of the first audit error that is of type T.
+    /// Like [downcast_mut](Audit::downcast_mut) this only checks the errors of the audit and its children,
+    /// if none of them matches the audit gets returned unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::fs::File;
+    /// use std::io;
+    /// use anyways::ext::AuditExt;
+    ///
+    /// let audit = File::open("./hello").wrap_err("Failed to open hello").unwrap_err();
+    /// let error: io::Error = audit.downcast().unwrap();
+    /// ```
+    pub fn downcast<T: Err>(mut self) -> Result<T, Audit> {
+        if let Some(pos) = self.errors.iter().position(|err| err.is::<T>()) {
+            let error: Box<dyn Error> = self.errors.remove(pos).error;
+            return Ok(*error.downcast::<T>().unwrap());
+        }
+
+        if let Some(extension) = self.extension.as_mut() {
+            for i in 0..extension.children.len() {
+                match extension.children.remove(i).downcast::<T>() {
+                    Ok(error) => return Ok(error),
+                    Err(child) => extension.children.insert(i, child),
+                }
             }
         }
 
-        None
+        Err(self)
+    }
+
+    /// Returns true if any error in the [chain](Audit::chain) is of type T.
+    pub fn is<T: Err>(&self) -> bool {
+        self.chain().any(|err| err.is::<T>())
+    }
+
+    /// Iterates through every error of the audit from the outermost to the innermost error.
+    /// After each audit error, the errors exposed through its [Error::source] are yielded.
+    /// The errors of the children come after the errors of their parent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::fs::File;
+    /// use anyways::ext::AuditExt;
+    ///
+    /// let audit = File::open("./hello").wrap_err("Failed to open hello").unwrap_err();
+    /// let messages: Vec<String> = audit.chain().map(|err| err.to_string()).collect();
+    /// assert_eq!(messages.len(), 2);
+    /// ```
+    pub fn chain(&self) -> Chain<'_> {
+        Chain {
+            errors: self.errors.iter(),
+            children: self.children().iter().rev().collect(),
+            next: None,
+        }
+    }
+
+    /// Returns the last error of the [chain](Audit::chain), if the audit has any errors.
+    pub fn root_cause(&self) -> Option<&(dyn Error + 'static)> {
+        self.chain().last()
+    }
+
+    /// Returns the code of the first error in the [chain](Audit::chain) which has one, see the [code](crate::code) module.
+    /// The [audit code](Audit::code) is used before the codes of the children.
+    pub fn get_code(&self) -> Option<&str> {
+        self.errors
+            .iter()
+            .find_map(|err| err.code.as_deref())
+            .or_else(|| self.code())
+            .or_else(|| self.children().iter().find_map(|child| child.get_code()))
+    }
+
+    /// Collapses the audit into a single line with the [code](Audit::get_code), the [chain](Audit::chain) of errors and the innermost error location,
+    /// for loggers which need one line per record. The same line is written by the alternate format `{:#}`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anyways::audit::Audit;
+    /// use anyways::ext::AuditExt;
+    ///
+    /// let audit = Err::<(), _>(Audit::new_empty()).wrap_err("open ./x").wrap_err("read plugin").unwrap_err();
+    /// assert!(audit.summary().starts_with("read plugin: open ./x (at "));
+    /// assert_eq!(audit.summary(), format!("{audit:#}"));
+    /// ```
+    pub fn summary(&self) -> String {
+        let mut out = String::new();
+        if let Some(code) = self.get_code() {
+            write!(out, "[{code}] ").unwrap();
+        }
+        for (i, err) in self.chain().enumerate() {
+            if i != 0 {
+                out.push_str(": ");
+            }
+            // Keep multi line messages on one line.
+            out.push_str(&err.to_string().lines().map(str::trim).collect::<Vec<_>>().join(" "));
+        }
+
+        if let Some(location) = self.innermost_location() {
+            write!(out, " (at {location})").unwrap();
+        }
+        out
+    }
+
+    /// The location of the innermost error which has one, these always point at user code as they are recorded through `#[track_caller]`.
+    fn innermost_location(&self) -> Option<&AuditLocation> {
+        self.children()
+            .iter()
+            .rev()
+            .find_map(|child| child.innermost_location())
+            .or_else(|| self.errors.iter().rev().find_map(|err| err.location.as_ref()))
+    }
+
+    /// Writes the audit as a single line JSON object, see the [json](crate::json) module for the schema.
+    pub fn to_json(&self) -> String {
+        crate::json::to_json(self)
     }
 
     /// Appends an error to the audit errors.
     ///
     /// # Arguments
     ///
-    /// * `err`: The error that you want to append. If you input an AuditError you can also set its location for backtrace location information.
+    /// * `err`: The error that you want to append. If the error does not have a location yet, the location of the caller gets used.
     ///
     /// # Examples
     ///
@@ -70,8 +304,14 @@ impl Audit {
     /// use anyways::ext::AuditExt;
     /// File::open("./hello").wrap_err("You blew stuff up");
     /// ```
+    #[track_caller]
     pub fn push_err(&mut self, err: impl Into<AuditError>) -> &mut Self {
-        self.errors.insert(0, err.into());
+        let mut err = err.into();
+        if err.location.is_none() {
+            err.location = Some(AuditLocation::caller());
+        }
+
+        self.errors.insert(0, err);
         self
     }
 
@@ -86,14 +326,16 @@ impl Audit {
     /// ```
     /// use std::fs::File;
     /// use owo_colors::{AnsiColors, DynColors};
-    /// use anyways::audit::AuditSection;
+    /// use anyways::audit::{AuditSection, AuditSectionKind};
     /// use anyways::ext::AuditExt;
     ///
     /// File::open("./hello").wrap(|audit| {
     ///     audit.push_section(AuditSection {
     ///         name: "Section Name stuff".to_string(),
     ///         color: DynColors::Ansi(AnsiColors::BrightBlue),
+    ///         kind: AuditSectionKind::Custom,
     ///         entries: vec![],
+    ///         footer: None,
     ///     });
     /// });
     /// ```
@@ -102,41 +344,103 @@ impl Audit {
     /// ```
     /// use std::fs::File;
     /// use owo_colors::{AnsiColors, DynColors};
-    /// use anyways::audit::AuditSection;
+    /// use anyways::audit::{AuditSection, AuditSectionKind};
     /// use anyways::ext::AuditExt;
     ///
     /// File::open("./hello").wrap_section_with(|| AuditSection {
     ///    name: "Section Name stuff".to_string(),
     ///    color: DynColors::Ansi(AnsiColors::BrightBlue),
+    ///    kind: AuditSectionKind::Custom,
     ///    entries: vec![],
+    ///    footer: None,
     ///});
     /// ```
     pub fn push_section(&mut self, section: impl Into<AuditSection>) -> &mut Self {
         self.custom_sections.push(section.into());
         self
     }
+
+    /// Appends a note, help, warning or suggestion to the Audit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anyways::audit::{Audit, AuditAttachmentKind};
+    ///
+    /// let mut audit = Audit::new("Failed to lock the database");
+    /// audit.push_attachment(AuditAttachmentKind::Suggestion, "run with --force");
+    /// ```
+    pub fn push_attachment(&mut self, kind: AuditAttachmentKind, message: impl Display) -> &mut Self {
+        self.extension_mut().attachments.push(AuditAttachment {
+            kind,
+            message: message.to_string(),
+        });
+        self
+    }
 }
 
 impl<E: Into<AuditError>> From<E> for Audit {
+    #[track_caller]
     fn from(error: E) -> Self {
-        let mut err: AuditError = error.into();
-        err.location = get_caller(2);
-        Audit::new(err)
+        Audit::new(error)
+    }
+}
+
+/// An iterator over the errors of an Audit and their sources. Created by [Audit::chain].
+#[derive(Clone)]
+pub struct Chain<'a> {
+    errors: std::slice::Iter<'a, AuditError>,
+    // The audits that still need to be walked, the next one is at the end.
+    children: Vec<&'a Audit>,
+    next: Option<&'a (dyn Error + 'static)>,
+}
+
+impl<'a> Iterator for Chain<'a> {
+    type Item = &'a (dyn Error + 'static);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let error = match self.next.take() {
+            Some(error) => error,
+            None => loop {
+                if let Some(error) = self.errors.next() {
+                    break &*error.error;
+                }
+
+                let child = self.children.pop()?;
+                self.errors = child.errors.iter();
+                self.children.extend(child.children().iter().rev());
+            },
+        };
+        #[cfg(any(feature = "anyhow", feature = "eyre"))]
+        let error = crate::interop::root_cause(error);
+
+        self.next = error.source();
+        Some(error)
     }
 }
 
 // this is actually the print error stuff
+impl Audit {
+    fn write_report(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let audit = get_audit_processor().process(self);
+        f.write_char('\n')?;
+        get_audit_formatter().format(f, &audit)
+    }
+}
+
 impl Debug for Audit {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        <Audit as Display>::fmt(self, f)
+        self.write_report(f)
     }
 }
 
 impl Display for Audit {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let audit = get_audit_processor().process(self);
-        f.write_char('\n')?;
-        get_audit_formatter().format(f, &audit)
+        if f.alternate() {
+            return f.write_str(&self.summary());
+        }
+
+        self.write_report(f)
     }
 }
 #[cfg(not(feature = "send"))]
@@ -154,7 +458,31 @@ pub trait Err = Error + 'static + SendReq + SyncReq;
 
 pub struct AuditError {
     pub error: Box<dyn Err>,
-    pub location: Option<Frame>,
+    /// Where the error was added to the audit, this is used to mark the error in the backtrace.
+    pub location: Option<AuditLocation>,
+    /// The name of the type the error was created from, messages are usually a `&str` or `String`.
+    pub type_name: &'static str,
+    /// The error code, see the [code](crate::code) module.
+    pub code: Option<String>,
+}
+
+impl AuditError {
+    /// Sets the error code of this error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::fs::File;
+    /// use anyways::audit::AuditError;
+    /// use anyways::ext::AuditExt;
+    ///
+    /// let audit = File::open("./plugin").wrap_err(AuditError::from("Failed to load plugin").with_code("APP-0042")).unwrap_err();
+    /// assert_eq!(audit.get_code(), Some("APP-0042"));
+    /// ```
+    pub fn with_code(mut self, code: impl Into<String>) -> AuditError {
+        self.code = Some(code.into());
+        self
+    }
 }
 
 impl<E: Into<Box<dyn Err>>> From<E> for AuditError {
@@ -162,6 +490,8 @@ impl<E: Into<Box<dyn Err>>> From<E> for AuditError {
         AuditError {
             error: err.into(),
             location: None,
+            type_name: std::any::type_name::<E>(),
+            code: None,
         }
     }
 }
@@ -180,12 +510,67 @@ impl DerefMut for AuditError {
     }
 }
 
+/// A location in the source code, usually recorded through `#[track_caller]`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AuditLocation {
+    pub file: Cow<'static, str>,
+    pub line: u32,
+    pub column: u32,
+}
+
+impl AuditLocation {
+    /// Returns the location of the caller, see [Location::caller].
+    #[track_caller]
+    pub fn caller() -> AuditLocation {
+        Location::caller().into()
+    }
+}
+
+impl From<&'static Location<'static>> for AuditLocation {
+    fn from(location: &'static Location<'static>) -> Self {
+        AuditLocation {
+            file: Cow::Borrowed(location.file()),
+            line: location.line(),
+            column: location.column(),
+        }
+    }
+}
+
+impl Display for AuditLocation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
+/// A short message attached to an audit, see [AuditExt::note](crate::ext::AuditExt::note).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuditAttachment {
+    pub kind: AuditAttachmentKind,
+    pub message: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AuditAttachmentKind {
+    /// Additional context about the failure.
+    Note,
+    /// How the user could fix the failure.
+    Help,
+    /// Something which could go wrong or has gone wrong besides the errors.
+    Warning,
+    /// A concrete action like a command or flag, shown in the help section.
+    Suggestion,
+}
+
 /// A custom section in the audit
 #[derive(Clone)]
 pub struct AuditSection {
     pub name: String,
     pub color: DynColors,
+    /// What the section contains, formatters can use this to lay out the section differently.
+    pub kind: AuditSectionKind,
     pub entries: Vec<AuditSectionEntry>,
+    /// A short note shown at the end of the section, like the amount of hidden backtrace frames.
+    pub footer: Option<String>,
 }
 
 impl AuditSection {
@@ -193,11 +578,24 @@ impl AuditSection {
         AuditSection {
             name: name.to_string(),
             color: DynColors::Ansi(AnsiColors::Magenta),
+            kind: AuditSectionKind::Custom,
             entries,
+            footer: None,
         }
     }
 }
 
+/// The kind of content in an AuditSection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuditSectionKind {
+    Custom,
+    /// The errors of the audit, each error entry has its label as the prefix_left.
+    Errors,
+    /// The backtrace frames grouped by file.
+    /// Each file starts with an entry that only has a [SpanStyle::File] text and files are separated by empty entries.
+    Backtrace,
+}
+
 /// An entry in an AuditSection.
 ///
 /// The entry consists of 4 parts.
@@ -212,15 +610,20 @@ impl AuditSection {
 /// │            -> ^ <- separator                                   │
 /// ╰────────────────────────────────────────────────────────────────╯
 /// ```
+///
+/// The depth indents the text to show that the entry belongs to the entry above it.
+///
+/// The parts are [StyledText] so every formatter can decide how the styles look.
 #[derive(Clone)]
 pub struct AuditSectionEntry {
-    pub prefix: Option<String>,
+    pub prefix: Option<StyledText>,
 
-    pub prefix_left: Option<String>,
-    pub separator: String,
-    pub prefix_right: Option<String>,
-    pub text: String,
-    pub suffix: Option<String>,
+    pub prefix_left: Option<StyledText>,
+    pub separator: StyledText,
+    pub prefix_right: Option<StyledText>,
+    pub text: StyledText,
+    pub suffix: Option<StyledText>,
+    pub depth: usize,
 }
 
 impl AuditSectionEntry {
@@ -228,20 +631,22 @@ impl AuditSectionEntry {
         AuditSectionEntry {
             prefix: None,
             prefix_left: None,
-            separator: "|".to_string(),
+            separator: StyledText::styled("|", SpanStyle::Separator),
             prefix_right: None,
-            text: "".to_string(),
+            text: StyledText::new(),
             suffix: None,
+            depth: 0,
         }
     }
-    pub fn text(text: String) -> AuditSectionEntry {
+    pub fn text(text: impl Into<StyledText>) -> AuditSectionEntry {
         AuditSectionEntry {
             prefix: None,
             prefix_left: None,
-            separator: "|".to_string(),
+            separator: StyledText::styled("|", SpanStyle::Separator),
             prefix_right: None,
-            text,
+            text: text.into(),
             suffix: None,
+            depth: 0,
         }
     }
 }
@@ -0,0 +1,32 @@
+//! Stable error codes and the links to their documentation.
+//!
+//! Codes are attached through [AuditExt::wrap_code](crate::ext::AuditExt::wrap_code) to the outermost error of an audit,
+//! to the audit itself if it has no errors yet,
+//! or [AuditError::with_code](crate::audit::AuditError::with_code) for a single error.
+//!
+//! # Examples
+//!
+//! ```
+//! anyways::code::set_url_template("https://docs.example/errors/{code}");
+//! assert_eq!(anyways::code::url("APP-0042").unwrap(), "https://docs.example/errors/APP-0042");
+//! ```
+
+use std::sync::RwLock;
+
+static URL_TEMPLATE: RwLock<Option<String>> = RwLock::new(None);
+
+/// Sets the template of the documentation links, `{code}` gets replaced with the error code.
+pub fn set_url_template(template: impl Into<String>) {
+    *URL_TEMPLATE.write().unwrap_or_else(|err| err.into_inner()) = Some(template.into());
+}
+
+/// Removes the template, error codes are shown without a link again.
+pub fn take_url_template() -> Option<String> {
+    URL_TEMPLATE.write().unwrap_or_else(|err| err.into_inner()).take()
+}
+
+/// Returns the documentation link of the code if a template has been set.
+pub fn url(code: &str) -> Option<String> {
+    let template = URL_TEMPLATE.read().unwrap_or_else(|err| err.into_inner());
+    template.as_ref().map(|template| template.replace("{code}", code))
+}
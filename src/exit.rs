@@ -0,0 +1,106 @@
+//! Exit codes for audits returned from `main`.
+//!
+//! The exit code of an audit is the code attached through [AuditExt::wrap_exit_code](crate::ext::AuditExt::wrap_exit_code),
+//! else the code registered for the first error type in the [chain](Audit::chain), else 1.
+//! A failing process never exits with success, so a code of 0 is reported as 1.
+
+use std::error::Error;
+use std::fmt::{Debug, Formatter};
+use std::process::{ExitCode, Termination};
+use std::sync::RwLock;
+
+use crate::audit::Audit;
+
+type Matcher = fn(&(dyn Error + 'static)) -> bool;
+
+static EXIT_CODES: RwLock<Vec<(Matcher, u8)>> = RwLock::new(Vec::new());
+
+/// Registers the exit code for audits which contain an error of type E.
+/// Codes registered later take priority over earlier ones.
+///
+/// # Examples
+///
+/// ```
+/// use std::io;
+/// use anyways::audit::Audit;
+///
+/// anyways::exit::register_exit_code::<io::Error>(74);
+/// assert_eq!(Audit::new(io::Error::other("Disk full")).get_exit_code(), 74);
+/// ```
+pub fn register_exit_code<E: Error + 'static>(code: u8) {
+    let mut codes = EXIT_CODES.write().unwrap_or_else(|err| err.into_inner());
+    codes.insert(0, (|err| err.is::<E>(), code));
+}
+
+impl Audit {
+    /// Returns the exit code a process should exit with when it fails with this audit, this is never 0.
+    pub fn get_exit_code(&self) -> u8 {
+        let code = self.find_exit_code().or_else(|| {
+            let codes = EXIT_CODES.read().unwrap_or_else(|err| err.into_inner());
+            self.chain()
+                .find_map(|err| codes.iter().find(|(matches, _)| matches(err)).map(|(_, code)| *code))
+        });
+
+        match code {
+            Some(0) | None => 1,
+            Some(code) => code,
+        }
+    }
+
+    fn find_exit_code(&self) -> Option<u8> {
+        self.exit_code()
+            .or_else(|| self.children().iter().find_map(|child| child.find_exit_code()))
+    }
+}
+
+/// The result of `main` which prints the audit through the installed formatter and exits with its [exit code](Audit::get_exit_code).
+///
+/// # Examples
+///
+/// ```no_run
+/// use anyways::exit::Report;
+/// use anyways::ext::AuditExt;
+///
+/// fn run() -> anyways::Result<()> {
+///     std::fs::read("./config.toml").wrap_err("Failed to read the config").wrap_exit_code(78)?;
+///     Ok(())
+/// }
+///
+/// fn main() -> Report {
+///     run().into()
+/// }
+/// ```
+pub struct Report(pub Result<(), Audit>);
+
+impl From<Result<(), Audit>> for Report {
+    fn from(result: Result<(), Audit>) -> Self {
+        Report(result)
+    }
+}
+
+impl From<Audit> for Report {
+    fn from(audit: Audit) -> Self {
+        Report(Err(audit))
+    }
+}
+
+impl Debug for Report {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.0 {
+            Ok(()) => f.write_str("Ok"),
+            Err(audit) => Debug::fmt(audit, f),
+        }
+    }
+}
+
+impl Termination for Report {
+    fn report(self) -> ExitCode {
+        match self.0 {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(audit) => {
+                eprintln!("{audit:?}");
+                ExitCode::from(audit.get_exit_code())
+            }
+        }
+    }
+}
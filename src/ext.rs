@@ -1,45 +1,136 @@
-use crate::audit::{Audit, AuditError, AuditSection};
-use backtrace::Frame;
+use std::fmt::Display;
+
+use crate::audit::{Audit, AuditAttachmentKind, AuditError, AuditLocation, AuditSection};
 
 pub trait AuditExt<T>: Sized {
+    #[track_caller]
     fn wrap_err<E: Into<AuditError>>(self, err: E) -> crate::Result<T> {
-	    self.wrap(|audit| {
+        let location = AuditLocation::caller();
+        self.wrap(|audit| {
             let mut e = err.into();
-            e.location = get_caller(3);
+            e.location.get_or_insert(location);
             audit.push_err(e);
         })
     }
 
+    #[track_caller]
     fn wrap_err_with<E: Into<AuditError>>(self, err: impl FnOnce() -> E) -> crate::Result<T> {
+        let location = AuditLocation::caller();
         self.wrap(|audit| {
             let mut e = err().into();
-            e.location = get_caller(3);
+            e.location.get_or_insert(location);
             audit.push_err(e);
         })
     }
 
+    #[track_caller]
     fn wrap_section(self, section: AuditSection) -> crate::Result<T> {
         self.wrap(|audit| {
             audit.push_section(section);
         })
     }
 
+    #[track_caller]
     fn wrap_section_with(self, section: impl FnOnce() -> AuditSection) -> crate::Result<T> {
         self.wrap(|audit| {
             audit.push_section(section());
         })
     }
 
+    /// Adds a note with extra context, notes are shown in the note section.
+    #[track_caller]
+    fn note(self, note: impl Display) -> crate::Result<T> {
+        self.wrap(|audit| {
+            audit.push_attachment(AuditAttachmentKind::Note, note);
+        })
+    }
+
+    /// Adds a note which is only created if the result is an error.
+    #[track_caller]
+    fn note_with<D: Display>(self, note: impl FnOnce() -> D) -> crate::Result<T> {
+        self.wrap(|audit| {
+            audit.push_attachment(AuditAttachmentKind::Note, note());
+        })
+    }
+
+    /// Adds a hint on how to fix the error, hints are shown in the help section.
+    #[track_caller]
+    fn help(self, help: impl Display) -> crate::Result<T> {
+        self.wrap(|audit| {
+            audit.push_attachment(AuditAttachmentKind::Help, help);
+        })
+    }
+
+    /// Adds a hint which is only created if the result is an error.
+    #[track_caller]
+    fn help_with<D: Display>(self, help: impl FnOnce() -> D) -> crate::Result<T> {
+        self.wrap(|audit| {
+            audit.push_attachment(AuditAttachmentKind::Help, help());
+        })
+    }
+
+    /// Adds a warning about something which may have caused the error, warnings are shown in the warning section.
+    #[track_caller]
+    fn warning(self, warning: impl Display) -> crate::Result<T> {
+        self.wrap(|audit| {
+            audit.push_attachment(AuditAttachmentKind::Warning, warning);
+        })
+    }
+
+    /// Adds a warning which is only created if the result is an error.
+    #[track_caller]
+    fn warning_with<D: Display>(self, warning: impl FnOnce() -> D) -> crate::Result<T> {
+        self.wrap(|audit| {
+            audit.push_attachment(AuditAttachmentKind::Warning, warning());
+        })
+    }
+
+    /// Suggests a concrete action like `run with --force`, suggestions are shown in the help section.
+    #[track_caller]
+    fn suggestion(self, suggestion: impl Display) -> crate::Result<T> {
+        self.wrap(|audit| {
+            audit.push_attachment(AuditAttachmentKind::Suggestion, suggestion);
+        })
+    }
+
+    /// Adds a suggestion which is only created if the result is an error.
+    #[track_caller]
+    fn suggestion_with<D: Display>(self, suggestion: impl FnOnce() -> D) -> crate::Result<T> {
+        self.wrap(|audit| {
+            audit.push_attachment(AuditAttachmentKind::Suggestion, suggestion());
+        })
+    }
+
+    /// Sets the error code of the outermost error, or of the audit itself if it has no errors, see the [code](crate::code) module.
+    #[track_caller]
+    fn wrap_code(self, code: impl Into<String>) -> crate::Result<T> {
+        let code = code.into();
+        self.wrap(|audit| match audit.errors.first_mut() {
+            Some(err) => err.code = Some(code),
+            None => {
+                audit.set_code(code);
+            }
+        })
+    }
+
+    /// Sets the exit code of the process if it fails with this audit, see the [exit](crate::exit) module.
+    #[track_caller]
+    fn wrap_exit_code(self, code: u8) -> crate::Result<T> {
+        self.wrap(|audit| {
+            audit.set_exit_code(code);
+        })
+    }
+
+    #[track_caller]
     fn wrap(self, func: impl FnOnce(&mut Audit)) -> crate::Result<T>;
 }
 
 impl<T, E: Into<AuditError>> AuditExt<T> for Result<T, E> {
+    #[track_caller]
     fn wrap(self, func: impl FnOnce(&mut Audit)) -> crate::Result<T> {
         match self {
             Ok(value) => Ok(value),
-            Err(audit) => {
-                let mut error = audit.into();
-                error.location = get_caller(2);
+            Err(error) => {
                 let mut audit = Audit::new(error);
                 func(&mut audit);
                 Err(audit)
@@ -49,6 +140,7 @@ impl<T, E: Into<AuditError>> AuditExt<T> for Result<T, E> {
 }
 
 impl<T> AuditExt<T> for Result<T, Audit> {
+    #[track_caller]
     fn wrap(self, func: impl FnOnce(&mut Audit)) -> crate::Result<T> {
         match self {
             Ok(value) => Ok(value),
@@ -61,6 +153,7 @@ impl<T> AuditExt<T> for Result<T, Audit> {
 }
 
 impl<T> AuditExt<T> for Option<T> {
+    #[track_caller]
     fn wrap(self, func: impl FnOnce(&mut Audit)) -> crate::Result<T> {
         match self {
             Some(value) => Ok(value),
@@ -72,17 +165,3 @@ impl<T> AuditExt<T> for Option<T> {
         }
     }
 }
-
-pub(crate) fn get_caller(extra_skips: i32) -> Option<Frame> {
-    let mut caller = None;
-    let mut remaining = 4 + extra_skips;
-    backtrace::trace(|frame| {
-        remaining -= 1;
-        if remaining == 0 {
-            caller = Some(frame.clone());
-        }
-        remaining > 0
-    });
-
-    caller
-}
\ No newline at end of file
@@ -1,25 +1,47 @@
-use owo_colors::{DynColors, OwoColorize};
+use owo_colors::{DynColors, OwoColorize, Style};
+use std::borrow::Cow;
+use std::env;
 use std::fmt;
-use std::fmt::{Formatter, Write};
+use std::fmt::Formatter;
+use std::io::{stderr, IsTerminal};
+use terminal_size::{terminal_size_of, Width};
 
 use crate::audit::{AuditSection, AuditSectionEntry};
-use crate::align::{align, Alignment, PaddingEntry};
+use crate::align::{align, display_width, Alignment, PaddingEntry};
+use crate::style::{Span, SpanStyle, StyledText};
 
-pub trait AuditFormatter: Sync {
+pub mod html;
+pub mod markdown;
+
+pub trait AuditFormatter: Send + Sync {
     fn format(&self, f: &mut Formatter, sections: &[AuditSection]) -> fmt::Result;
 }
 
 pub struct AnywaysAuditFormatter {
     /// The total width of the section
     pub width: u32,
+    /// If the width should be taken from `COLUMNS` or the size of the terminal, [width](Self::width) is used if neither is available.
+    pub detect_width: bool,
     /// The amount of padding on both sides of the section entries.
     pub side_padding: u32,
     /// If the section should be in a simplified view
     pub simple_section: bool,
 
+    /// The width of the prefix column.
     pub prefix_padding: usize,
+    /// The width of the prefix_left column.
     pub prefix_left_padding: usize,
+    /// The width of the separator column.
+    pub separator_padding: usize,
+    /// The width of the prefix_right column.
     pub prefix_right_padding: usize,
+
+    /// The colors used for each [SpanStyle].
+    pub theme: AnsiTheme,
+    /// If the output should be colored.
+    pub color: ColorChoice,
+    /// If the section borders and arrows should use unicode characters.
+    pub charset: Charset,
 }
 
 impl AuditFormatter for AnywaysAuditFormatter {
@@ -32,39 +54,255 @@ impl Default for AnywaysAuditFormatter {
     fn default() -> Self {
         AnywaysAuditFormatter {
             width: 120,
+            detect_width: false,
             side_padding: 1,
             simple_section: false,
             prefix_padding: 3,
             prefix_left_padding: 8,
-            prefix_right_padding: 12
+            separator_padding: 3,
+            prefix_right_padding: 10,
+            theme: AnsiTheme::default(),
+            color: ColorChoice::Auto,
+            charset: Charset::Auto,
+        }
+    }
+}
+
+/// When the output should be colored.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Colors the output if stderr is a terminal.
+    /// `NO_COLOR` and `TERM=dumb` disable the colors while `CLICOLOR_FORCE` forces them.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn should_color(self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                if env_flag("CLICOLOR_FORCE") {
+                    true
+                } else if env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) || is_dumb_terminal() {
+                    false
+                } else {
+                    stderr().is_terminal()
+                }
+            }
+        }
+    }
+}
+
+/// Which characters are used to draw the sections.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Charset {
+    /// Uses unicode if stderr is a terminal which is not `TERM=dumb`.
+    #[default]
+    Auto,
+    Unicode,
+    Ascii,
+}
+
+impl Charset {
+    pub fn is_unicode(self) -> bool {
+        match self {
+            Charset::Unicode => true,
+            Charset::Ascii => false,
+            Charset::Auto => !is_dumb_terminal() && stderr().is_terminal(),
+        }
+    }
+}
+
+// A flag is set if it is not empty and not 0, NO_COLOR instead turns color off for any non-empty value.
+fn env_flag(name: &str) -> bool {
+    env::var_os(name).is_some_and(|value| !value.is_empty() && value != "0")
+}
+
+fn is_dumb_terminal() -> bool {
+    env::var_os("TERM").is_some_and(|term| term == "dumb")
+}
+
+/// The characters used to draw a section.
+struct Glyphs {
+    header: &'static str,
+    simple_header: &'static str,
+    horizontal: &'static str,
+    vertical: &'static str,
+    top_right: &'static str,
+    bottom_left: &'static str,
+    bottom_right: &'static str,
+}
+
+const UNICODE: Glyphs = Glyphs {
+    header: "╭── ",
+    simple_header: "==> ",
+    horizontal: "─",
+    vertical: "│",
+    top_right: "╮",
+    bottom_left: "╰",
+    bottom_right: "╯",
+};
+
+const ASCII: Glyphs = Glyphs {
+    header: "+-- ",
+    simple_header: "==> ",
+    horizontal: "-",
+    vertical: "|",
+    top_right: "+",
+    bottom_left: "+",
+    bottom_right: "+",
+};
+
+/// How the current output looks, resolved once per format.
+struct Output {
+    width: usize,
+    color: bool,
+    unicode: bool,
+    glyphs: &'static Glyphs,
+}
+
+impl Output {
+    fn paint(&self, text: &str, style: Style) -> String {
+        if self.color {
+            text.style(style).to_string()
+        } else {
+            text.to_string()
+        }
+    }
+
+    fn paint_color(&self, text: &str, color: DynColors) -> String {
+        self.paint(text, Style::new().color(color))
+    }
+
+    /// Replaces the arrows used by the processor if the output is not unicode.
+    fn text<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if self.unicode || text.is_ascii() {
+            return text.into();
+        }
+
+        text.chars()
+            .map(|ch| match ch {
+                '↓' => "v".to_string(),
+                '→' => ">".to_string(),
+                '↳' => "`-".to_string(),
+                '─' => "-".to_string(),
+                '│' => "|".to_string(),
+                ch => ch.to_string(),
+            })
+            .collect::<String>()
+            .into()
+    }
+}
+
+/// The terminal styles of each [SpanStyle].
+#[derive(Clone)]
+pub struct AnsiTheme {
+    pub location: Style,
+    pub module: Style,
+    pub error_label: Style,
+    pub replaced: Style,
+    pub file: Style,
+    pub external_file: Style,
+    pub separator: Style,
+    pub error_marker: Style,
+    pub code: Style,
+    pub highlight: Style,
+    pub error_code: Style,
+    pub link: Style,
+}
+
+impl AnsiTheme {
+    pub fn get(&self, style: SpanStyle) -> Option<Style> {
+        match style {
+            SpanStyle::Plain => None,
+            SpanStyle::Location => Some(self.location),
+            SpanStyle::Module => Some(self.module),
+            SpanStyle::ErrorLabel => Some(self.error_label),
+            SpanStyle::Replaced => Some(self.replaced),
+            SpanStyle::File => Some(self.file),
+            SpanStyle::ExternalFile => Some(self.external_file),
+            SpanStyle::Separator => Some(self.separator),
+            SpanStyle::ErrorMarker => Some(self.error_marker),
+            SpanStyle::Code => Some(self.code),
+            SpanStyle::Highlight => Some(self.highlight),
+            SpanStyle::ErrorCode => Some(self.error_code),
+            SpanStyle::Link => Some(self.link),
+        }
+    }
+}
+
+impl Default for AnsiTheme {
+    fn default() -> Self {
+        AnsiTheme {
+            location: Style::new().blue(),
+            module: Style::new().purple(),
+            error_label: Style::new().red(),
+            replaced: Style::new().cyan(),
+            file: Style::new().white().bold(),
+            external_file: Style::new().white(),
+            separator: Style::new().white(),
+            error_marker: Style::new().red().bold(),
+            code: Style::new().dimmed(),
+            highlight: Style::new().red().bold().underline(),
+            error_code: Style::new().yellow().bold(),
+            link: Style::new().blue().underline(),
         }
     }
 }
 
 impl AnywaysAuditFormatter {
     pub fn format(&self, f: &mut Formatter<'_>, sections: &[AuditSection]) -> fmt::Result {
+        let unicode = self.charset.is_unicode();
+        let out = Output {
+            width: self.get_width(),
+            color: self.color.should_color(),
+            unicode,
+            glyphs: if unicode { &UNICODE } else { &ASCII },
+        };
+
         for section in sections {
-            self.write_section_header(f, &section.name, section.color)?;
+            self.write_section_header(f, &out, &section.name, section.color)?;
             for entry in &section.entries {
-                self.write_section_entry(f, entry, section.color)?;
+                self.write_section_entry(f, &out, entry, section.color)?;
             }
-            self.write_section_footer(f, section.color)?;
+            self.write_section_footer(f, &out, section.footer.as_deref(), section.color)?;
         }
 
         Ok(())
     }
 
+    /// Returns the total width of the sections.
+    pub fn get_width(&self) -> usize {
+        // The borders and the section header need some space.
+        const MIN_WIDTH: usize = 20;
+
+        let mut width = self.width as usize;
+        if self.detect_width {
+            let columns = env::var("COLUMNS").ok().and_then(|v| v.trim().parse().ok());
+            if let Some(columns) = columns.or_else(|| terminal_size_of(stderr()).map(|(Width(w), _)| w as usize)) {
+                width = columns;
+            }
+        }
+
+        width.max(MIN_WIDTH)
+    }
+
     fn write_section_entry(
         &self,
         f: &mut Formatter<'_>,
+        out: &Output,
         entry: &AuditSectionEntry,
         color: DynColors,
     ) -> fmt::Result {
         let mut entries = Vec::new();
         // Prefix
         entries.push(PaddingEntry {
-            text: entry.prefix.as_deref().unwrap_or("").to_string(),
-            width: 3,
+            text: entry.prefix.as_ref().map(|v| self.render(out, v)).unwrap_or_default(),
+            width: self.prefix_padding,
             alignment: Alignment::Left,
         });
 
@@ -72,18 +310,16 @@ impl AnywaysAuditFormatter {
         // Prefix Left
         if let Some(value) = &entry.prefix_left {
             entries.push(PaddingEntry {
-                text: value.clone(),
-                width: 8,
+                text: self.render(out, value),
+                width: self.prefix_left_padding,
                 alignment: Alignment::Right,
             });
         }
         // Prefix Separator
         if entry.prefix_left.is_some() || entry.prefix_right.is_some() {
             entries.push(PaddingEntry {
-                text: {
-                    entry.separator.white().to_string()
-                },
-                width: 3,
+                text: self.render(out, &entry.separator),
+                width: self.separator_padding,
                 alignment: Alignment::Center,
             });
         }
@@ -91,8 +327,8 @@ impl AnywaysAuditFormatter {
         // Prefix Right
         if let Some(value) = &entry.prefix_right {
             entries.push(PaddingEntry {
-                text: value.clone(),
-                width: 10,
+                text: self.render(out, value),
+                width: self.prefix_right_padding,
                 alignment: Alignment::Left,
             });
         }
@@ -100,52 +336,81 @@ impl AnywaysAuditFormatter {
 
         // Text
         entries.push(PaddingEntry {
-            text: entry.text.clone(),
+            text: format!("{}{}", "  ".repeat(entry.depth), self.render(out, &entry.text)),
             width: 0,
             alignment: Alignment::Left,
         });
 
         let pad = " ".repeat(self.side_padding as usize);
-        let s = "│".color(color).to_string();
-        let max_width = 116;
+        let s = out.paint_color(out.glyphs.vertical, color);
+        // The width inside of the borders and side padding.
+        let max_width = out.width.saturating_sub(2 + 2 * self.side_padding as usize).max(1);
         align(&entries, max_width, ' ', |line| {
-            let mut fill = " ".repeat(max_width.saturating_sub(get_length(&line)));
+            let fill = " ".repeat(max_width.saturating_sub(get_length(&line)));
             writeln!(f, "{s}{pad}{line}{fill}{pad}{s}")
         })?;
         Ok(())
     }
 
+    /// Renders the text with the colors of the theme.
+    fn render(&self, out: &Output, text: &StyledText) -> String {
+        let mut rendered = String::new();
+        for span in &text.spans {
+            let text = out.text(&span.text);
+            match self.theme.get(span.style) {
+                Some(style) => rendered.push_str(&out.paint(&text, style)),
+                None => rendered.push_str(&text),
+            }
+        }
+        rendered
+    }
+
     fn write_section_header(
         &self,
         f: &mut Formatter<'_>,
+        out: &Output,
         text: &str,
         color: DynColors,
     ) -> fmt::Result {
+        let title = out.paint(text, Style::new().bold());
         if self.simple_section {
-            writeln!(f, "{}{}", "==> ".color(color), text.bold(),)
+            writeln!(f, "{}{}", out.paint_color(out.glyphs.simple_header, color), title)
         } else {
             writeln!(
                 f,
                 "{}{} {}{}",
-                "╭── ".color(color),
-                text.bold(),
-                create_pad(&"─".color(color).to_string(), text, self.width as usize - 6),
-                "╮".color(color)
+                out.paint_color(out.glyphs.header, color),
+                title,
+                create_pad(&out.paint_color(out.glyphs.horizontal, color), text, out.width.saturating_sub(6)),
+                out.paint_color(out.glyphs.top_right, color)
             )
         }
     }
 
-    fn write_section_footer(&self, f: &mut Formatter<'_>, color: DynColors) -> fmt::Result {
-        if !self.simple_section {
-            writeln!(
+    fn write_section_footer(&self, f: &mut Formatter<'_>, out: &Output, footer: Option<&str>, color: DynColors) -> fmt::Result {
+        let horizontal = out.paint_color(out.glyphs.horizontal, color);
+        match (self.simple_section, footer) {
+            (false, Some(footer)) => writeln!(
+                f,
+                "{}{} {} {}{}",
+                out.paint_color(out.glyphs.bottom_left, color),
+                horizontal.repeat(2),
+                out.paint(footer, self.theme.separator),
+                create_pad(&horizontal, footer, out.width.saturating_sub(6)),
+                out.paint_color(out.glyphs.bottom_right, color)
+            ),
+            (false, None) => writeln!(
                 f,
                 "{}{}{}",
-                "╰".color(color),
-                "─".color(color).to_string().repeat(self.width as usize - 2),
-                "╯".color(color)
-            )
-        } else {
-            writeln!(f)
+                out.paint_color(out.glyphs.bottom_left, color),
+                horizontal.repeat(out.width.saturating_sub(2)),
+                out.paint_color(out.glyphs.bottom_right, color)
+            ),
+            (true, Some(footer)) => {
+                writeln!(f, "{}", out.paint(footer, self.theme.separator))?;
+                writeln!(f)
+            }
+            (true, None) => writeln!(f),
         }
     }
 }
@@ -155,24 +420,31 @@ pub fn create_pad(value: &str, text: &str, length: usize) -> String {
     value.repeat(length.saturating_sub(get_length(text)))
 }
 
-// Get length of a string skipping all ansi color codes.
+// Get the display width of a string skipping all ansi escape codes.
 pub fn get_length(text: &str) -> usize {
-    let mut wait_m = false;
-    let mut len = 0;
-    for ch in text.chars() {
-        if wait_m {
-            if ch == 'm' {
-                wait_m = false;
-            }
+    display_width(text)
+}
 
-            continue;
-        } else if ch == '\x1b' {
-            wait_m = true;
-            continue;
-        } else {
-            len += 1;
-        }
+/// Returns the path span if the entry is the header of a backtrace file.
+pub(crate) fn file_header(entry: &AuditSectionEntry) -> Option<&Span> {
+    match entry.text.spans.as_slice() {
+        [span] if matches!(span.style, SpanStyle::File | SpanStyle::ExternalFile) && entry.prefix_left.is_none() => Some(span),
+        _ => None,
     }
+}
 
-    len
+/// Escapes the characters which have a meaning in HTML.
+pub(crate) fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            ch => out.push(ch),
+        }
+    }
+    out
 }
@@ -0,0 +1,270 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use owo_colors::{AnsiColors, DynColors};
+
+use crate::audit::{Audit, AuditSection, AuditSectionEntry, AuditSectionKind};
+use crate::formatter::{escape_html, file_header, AuditFormatter};
+use crate::get_audit_processor;
+use crate::style::{Span, SpanStyle, StyledText};
+
+const STYLE: &str = r#"
+body { font-family: system-ui, sans-serif; background: #f6f7f9; color: #1f2328; margin: 2em auto; max-width: 1100px; padding: 0 1em; }
+h1 { font-size: 1.5em; }
+.section { background: #fff; border: 1px solid #d0d7de; border-left: 6px solid var(--color); border-radius: 6px; margin: 1em 0; padding: 0.5em 1em 1em; }
+.section h2 { font-size: 1.1em; color: var(--color); }
+.section .footer { color: #8c959f; font-size: 0.9em; margin: 0.5em 0 0; }
+.errors { list-style: none; padding: 0; margin: 0; }
+.errors li { padding: 0.2em 0 0.2em calc(var(--depth) * 1.5em); }
+.errors li:target { background: #fff8c5; }
+details.file { margin: 0.4em 0; }
+details.file > summary { cursor: pointer; font-family: monospace; font-weight: bold; padding: 0.2em 0; }
+details.file.external > summary { font-weight: normal; color: #57606a; }
+table.frames { border-collapse: collapse; font-family: monospace; font-size: 0.9em; width: 100%; }
+table.frames td { padding: 0.1em 0.5em; white-space: pre-wrap; vertical-align: top; }
+table.frames td.labels, table.frames td.location { text-align: right; white-space: nowrap; width: 1%; }
+table.frames tr.error { background: #ffebe9; }
+table.frames a:target { outline: 2px solid #cf222e; }
+a { color: inherit; }
+.location { color: #0969da; }
+.module { color: #8250df; }
+.label { color: #cf222e; font-weight: bold; }
+.replaced { color: #1b7c83; }
+.separator { color: #8c959f; }
+.marker { color: #cf222e; font-weight: bold; }
+.code { color: #57606a; }
+.error-code { color: #9a6700; font-weight: bold; }
+.highlight { color: #cf222e; font-weight: bold; text-decoration: underline; }
+"#;
+
+/// Formats the audit as a self-contained HTML page which can be opened in any browser.
+///
+/// The error labels link to the frames they were created in,
+/// files outside of the current project are collapsed.
+pub struct HtmlAuditFormatter {
+    /// The title of the page.
+    pub title: String,
+    /// If files outside of the current project like the standard library or dependencies should be collapsed.
+    pub collapse_external: bool,
+}
+
+impl Default for HtmlAuditFormatter {
+    fn default() -> Self {
+        HtmlAuditFormatter {
+            title: "Error report".to_string(),
+            collapse_external: true,
+        }
+    }
+}
+
+impl AuditFormatter for HtmlAuditFormatter {
+    fn format(&self, f: &mut Formatter, sections: &[AuditSection]) -> fmt::Result {
+        // Only link to frames which are in the report.
+        let frames: HashSet<String> = sections
+            .iter()
+            .filter(|section| section.kind == AuditSectionKind::Backtrace)
+            .flat_map(|section| &section.entries)
+            .filter_map(|entry| entry.prefix.as_ref())
+            .flat_map(labels)
+            .collect();
+
+        writeln!(f, "<!DOCTYPE html>")?;
+        writeln!(f, "<html lang=\"en\">")?;
+        writeln!(f, "<head>")?;
+        writeln!(f, "<meta charset=\"utf-8\">")?;
+        writeln!(f, "<title>{}</title>", escape_html(&self.title))?;
+        writeln!(f, "<style>{STYLE}</style>")?;
+        writeln!(f, "</head>")?;
+        writeln!(f, "<body>")?;
+        writeln!(f, "<h1>{}</h1>", escape_html(&self.title))?;
+        for section in sections {
+            writeln!(
+                f,
+                "<section class=\"section\" style=\"--color: {}\">",
+                css_color(section.color)
+            )?;
+            writeln!(f, "<h2>{}</h2>", escape_html(&section.name))?;
+            match section.kind {
+                AuditSectionKind::Errors => self.write_errors(f, section, &frames)?,
+                AuditSectionKind::Backtrace => self.write_backtrace(f, section)?,
+                AuditSectionKind::Custom => self.write_custom(f, section)?,
+            }
+            if let Some(footer) = &section.footer {
+                writeln!(f, "<p class=\"footer\">{}</p>", escape_html(footer))?;
+            }
+            writeln!(f, "</section>")?;
+        }
+        writeln!(f, "</body>")?;
+        writeln!(f, "</html>")
+    }
+}
+
+impl HtmlAuditFormatter {
+    /// Processes the audit with the installed processor and writes the page to the path.
+    pub fn save(&self, audit: &Audit, path: impl AsRef<Path>) -> io::Result<()> {
+        let sections = get_audit_processor().process(audit);
+        fs::write(path, Page(self, &sections).to_string())
+    }
+
+    fn write_errors(&self, f: &mut Formatter<'_>, section: &AuditSection, frames: &HashSet<String>) -> fmt::Result {
+        writeln!(f, "<ul class=\"errors\">")?;
+        for entry in &section.entries {
+            let label = entry.prefix_left.as_ref().map(|v| v.to_string()).unwrap_or_default();
+            if label.is_empty() {
+                write!(f, "<li style=\"--depth: {}\">", entry.depth)?;
+            } else {
+                write!(f, "<li id=\"error-{0}\" style=\"--depth: {1}\">", escape_html(&label), entry.depth)?;
+                if frames.contains(&label) {
+                    write!(f, "<a class=\"label\" href=\"#frame-{0}\">{0}</a> ", escape_html(&label))?;
+                } else {
+                    write!(f, "<span class=\"label\">{}</span> ", escape_html(&label))?;
+                }
+            }
+            writeln!(f, "{}</li>", spans(&entry.text))?;
+        }
+        writeln!(f, "</ul>")
+    }
+
+    fn write_backtrace(&self, f: &mut Formatter<'_>, section: &AuditSection) -> fmt::Result {
+        let mut files: Vec<(&Span, Vec<&AuditSectionEntry>)> = Vec::new();
+        for entry in &section.entries {
+            if let Some(file) = file_header(entry) {
+                files.push((file, Vec::new()));
+            } else if !entry.text.is_empty() || entry.prefix_left.is_some() {
+                if let Some((_, frames)) = files.last_mut() {
+                    frames.push(entry);
+                }
+            }
+        }
+
+        for (file, frames) in files {
+            // Files with errors in them always stay open.
+            let external = file.style == SpanStyle::ExternalFile;
+            let has_errors = frames.iter().any(|frame| frame.prefix.as_ref().is_some_and(|v| !v.is_empty()));
+            writeln!(
+                f,
+                "<details class=\"file{}\"{}>",
+                if external { " external" } else { "" },
+                if external && self.collapse_external && !has_errors { "" } else { " open" }
+            )?;
+            writeln!(f, "<summary>{}</summary>", escape_html(&file.text))?;
+            writeln!(f, "<table class=\"frames\">")?;
+            for frame in frames {
+                self.write_frame(f, frame)?;
+            }
+            writeln!(f, "</table>")?;
+            writeln!(f, "</details>")?;
+        }
+
+        Ok(())
+    }
+
+    fn write_frame(&self, f: &mut Formatter<'_>, entry: &AuditSectionEntry) -> fmt::Result {
+        let labels = entry.prefix.as_ref().map(labels).unwrap_or_default();
+        write!(f, "{}", if labels.is_empty() { "<tr>" } else { "<tr class=\"error\">" })?;
+
+        write!(f, "<td class=\"labels\">")?;
+        for (i, label) in labels.iter().enumerate() {
+            if i != 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "<a id=\"frame-{0}\" class=\"label\" href=\"#error-{0}\">{0}</a>", escape_html(label))?;
+        }
+        write!(f, "</td>")?;
+
+        let optional = |text: &Option<StyledText>| text.as_ref().map(spans).unwrap_or_default();
+        write!(f, "<td class=\"location\">{}</td>", optional(&entry.prefix_left))?;
+        write!(f, "<td>{}</td>", spans(&entry.separator))?;
+        write!(f, "<td>")?;
+        if let Some(module) = entry.prefix_right.as_ref().filter(|v| !v.is_empty()) {
+            write!(f, "{}<span class=\"separator\">::</span>", spans(module))?;
+        }
+        writeln!(f, "{}</td></tr>", spans(&entry.text))
+    }
+
+    fn write_custom(&self, f: &mut Formatter<'_>, section: &AuditSection) -> fmt::Result {
+        writeln!(f, "<ul class=\"errors\">")?;
+        for entry in &section.entries {
+            if entry.text.is_empty() {
+                continue;
+            }
+
+            write!(f, "<li style=\"--depth: {}\">", entry.depth)?;
+            if let Some(prefix) = entry.prefix_left.as_ref().filter(|v| !v.is_empty()) {
+                write!(f, "<strong>{}</strong> ", spans(prefix))?;
+            }
+            writeln!(f, "{}</li>", spans(&entry.text))?;
+        }
+        writeln!(f, "</ul>")
+    }
+}
+
+struct Page<'a>(&'a HtmlAuditFormatter, &'a [AuditSection]);
+
+impl Display for Page<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.format(f, self.1)
+    }
+}
+
+/// The error labels of a frame prefix.
+fn labels(prefix: &StyledText) -> Vec<String> {
+    prefix
+        .spans
+        .iter()
+        .filter(|span| span.style == SpanStyle::ErrorLabel)
+        .map(|span| span.text.clone())
+        .collect()
+}
+
+/// Writes every span as an escaped html element with the class of its style.
+fn spans(text: &StyledText) -> String {
+    let mut out = String::new();
+    for span in &text.spans {
+        let class = match span.style {
+            SpanStyle::Plain | SpanStyle::File | SpanStyle::ExternalFile => None,
+            SpanStyle::Location => Some("location"),
+            SpanStyle::Module => Some("module"),
+            SpanStyle::ErrorLabel => Some("label"),
+            SpanStyle::Replaced => Some("replaced"),
+            SpanStyle::Separator => Some("separator"),
+            SpanStyle::ErrorMarker => Some("marker"),
+            SpanStyle::Code => Some("code"),
+            SpanStyle::Highlight => Some("highlight"),
+            SpanStyle::ErrorCode => Some("error-code"),
+            SpanStyle::Link => {
+                let url = escape_html(&span.text);
+                out.push_str(&format!("<a href=\"{url}\">{url}</a>"));
+                continue;
+            }
+        };
+
+        match class {
+            Some(class) => out.push_str(&format!("<span class=\"{class}\">{}</span>", escape_html(&span.text))),
+            None => out.push_str(&escape_html(&span.text)),
+        }
+    }
+    out
+}
+
+fn css_color(color: DynColors) -> String {
+    match color {
+        DynColors::Rgb(r, g, b) => format!("rgb({r}, {g}, {b})"),
+        DynColors::Ansi(color) => match color {
+            AnsiColors::Black | AnsiColors::BrightBlack => "#57606a",
+            AnsiColors::Red | AnsiColors::BrightRed => "#cf222e",
+            AnsiColors::Green | AnsiColors::BrightGreen => "#1a7f37",
+            AnsiColors::Yellow | AnsiColors::BrightYellow => "#bf8700",
+            AnsiColors::Blue | AnsiColors::BrightBlue => "#0969da",
+            AnsiColors::Magenta | AnsiColors::BrightMagenta => "#8250df",
+            AnsiColors::Cyan | AnsiColors::BrightCyan => "#1b7c83",
+            AnsiColors::White | AnsiColors::BrightWhite | AnsiColors::Default => "#8c959f",
+        }
+        .to_string(),
+        _ => "#8c959f".to_string(),
+    }
+}
@@ -0,0 +1,143 @@
+use std::fmt;
+use std::fmt::{Formatter, Write};
+
+use crate::audit::{AuditSection, AuditSectionEntry, AuditSectionKind};
+use crate::formatter::{escape_html, file_header, AuditFormatter};
+use crate::style::StyledText;
+
+/// Formats the audit as GitHub flavored Markdown which can be pasted into issues and pull requests.
+///
+/// Every section gets a heading, errors become an ordered list
+/// and the files of the backtrace become collapsible `<details>` blocks.
+pub struct MarkdownAuditFormatter {
+    /// The heading level of the section headings.
+    pub heading_level: usize,
+    /// If the backtrace files should be collapsed by default.
+    pub collapse_files: bool,
+}
+
+impl Default for MarkdownAuditFormatter {
+    fn default() -> Self {
+        MarkdownAuditFormatter {
+            heading_level: 3,
+            collapse_files: true,
+        }
+    }
+}
+
+impl AuditFormatter for MarkdownAuditFormatter {
+    fn format(&self, f: &mut Formatter, sections: &[AuditSection]) -> fmt::Result {
+        for section in sections {
+            writeln!(f, "{} {}", "#".repeat(self.heading_level.clamp(1, 6)), escape(&section.name))?;
+            writeln!(f)?;
+            match section.kind {
+                AuditSectionKind::Errors => self.write_errors(f, section)?,
+                AuditSectionKind::Backtrace => self.write_backtrace(f, section)?,
+                AuditSectionKind::Custom => self.write_custom(f, section)?,
+            }
+            if let Some(footer) = &section.footer {
+                writeln!(f)?;
+                writeln!(f, "*{}*", escape(footer))?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl MarkdownAuditFormatter {
+    fn write_errors(&self, f: &mut Formatter<'_>, section: &AuditSection) -> fmt::Result {
+        for entry in &section.entries {
+            let indent = "   ".repeat(entry.depth);
+            match entry.prefix_left.as_ref().filter(|label| !label.is_empty()) {
+                Some(label) => writeln!(f, "{indent}1. `{label}` {}", escape(&entry.text.to_string()))?,
+                None => writeln!(f, "{indent}- {}", escape(&entry.text.to_string()))?,
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_backtrace(&self, f: &mut Formatter<'_>, section: &AuditSection) -> fmt::Result {
+        let mut open = false;
+        for entry in &section.entries {
+            if let Some(file) = file_header(entry) {
+                if open {
+                    self.close_file(f)?;
+                }
+
+                let details = if self.collapse_files { "<details>" } else { "<details open>" };
+                writeln!(f, "{details}")?;
+                writeln!(f, "<summary><code>{}</code></summary>", escape_html(&file.text))?;
+                writeln!(f)?;
+                writeln!(f, "```")?;
+                open = true;
+            } else if entry.text.is_empty() && entry.prefix_left.is_none() {
+                // The spacer between files
+                continue;
+            } else {
+                writeln!(f, "{}", frame_line(entry))?;
+            }
+        }
+
+        if open {
+            self.close_file(f)?;
+        }
+        Ok(())
+    }
+
+    fn close_file(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "```")?;
+        writeln!(f)?;
+        writeln!(f, "</details>")
+    }
+
+    fn write_custom(&self, f: &mut Formatter<'_>, section: &AuditSection) -> fmt::Result {
+        for entry in &section.entries {
+            if entry.text.is_empty() {
+                continue;
+            }
+
+            let indent = "  ".repeat(entry.depth);
+            match entry.prefix_left.as_ref().filter(|prefix| !prefix.is_empty()) {
+                Some(prefix) => writeln!(f, "{indent}- **{}** {}", escape(&prefix.to_string()), escape(&entry.text.to_string()))?,
+                None => writeln!(f, "{indent}- {}", escape(&entry.text.to_string()))?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes all parts of a frame on one line, the module and the function are joined back into a path.
+fn frame_line(entry: &AuditSectionEntry) -> String {
+    let text = |text: &Option<StyledText>| text.as_ref().map(|v| v.to_string()).unwrap_or_default();
+
+    let mut line = String::new();
+    write!(
+        line,
+        "{:<6}{:>8} {} ",
+        text(&entry.prefix),
+        text(&entry.prefix_left),
+        entry.separator
+    )
+    .unwrap();
+    if let Some(module) = entry.prefix_right.as_ref().filter(|v| !v.is_empty()) {
+        write!(line, "{module}::").unwrap();
+    }
+    write!(line, "{}", entry.text).unwrap();
+    line
+}
+
+/// Escapes the characters which would be interpreted as Markdown.
+pub(crate) fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        if matches!(ch, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|') {
+            out.push('\\');
+        }
+        out.push(ch);
+    }
+    out
+}
@@ -0,0 +1,90 @@
+//! Thread-safe storage for the globally installed hooks.
+
+use std::cell::RefCell;
+use std::sync::{Arc, PoisonError, RwLock};
+
+/// A slot holding a hook that can be read and replaced from any thread.
+pub(crate) struct Hook<T: ?Sized> {
+    value: RwLock<Option<Arc<T>>>,
+}
+
+impl<T: ?Sized> Hook<T> {
+    pub const fn new() -> Hook<T> {
+        Hook {
+            value: RwLock::new(None),
+        }
+    }
+
+    /// Returns the currently installed hook.
+    pub fn get(&self) -> Option<Arc<T>> {
+        // A panic while holding the lock cannot leave an Option<Arc> half written, so poisoning is ignored.
+        self.value
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Replaces the hook and returns the previous one.
+    pub fn set(&self, value: Arc<T>) -> Option<Arc<T>> {
+        self.value
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .replace(value)
+    }
+
+    /// Installs the hook if the slot is empty, else hands the value back.
+    pub fn install(&self, value: Arc<T>) -> Result<(), Arc<T>> {
+        let mut slot = self.value.write().unwrap_or_else(PoisonError::into_inner);
+        if slot.is_some() {
+            return Err(value);
+        }
+
+        *slot = Some(value);
+        Ok(())
+    }
+
+    /// Removes the hook and returns it.
+    pub fn take(&self) -> Option<Arc<T>> {
+        self.value
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.value
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .is_some()
+    }
+}
+
+/// A stack of hooks that take priority over a global [Hook]. This is meant to be stored in a thread local.
+pub(crate) struct ScopedHooks<T: ?Sized> {
+    stack: RefCell<Vec<Arc<T>>>,
+}
+
+impl<T: ?Sized> ScopedHooks<T> {
+    pub const fn new() -> ScopedHooks<T> {
+        ScopedHooks {
+            stack: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Returns the innermost hook.
+    pub fn last(&self) -> Option<Arc<T>> {
+        self.stack.borrow().last().cloned()
+    }
+
+    /// Pushes a hook and returns the depth the stack had before, which is used to pop it again.
+    pub fn push(&self, value: Arc<T>) -> usize {
+        let mut stack = self.stack.borrow_mut();
+        stack.push(value);
+        stack.len() - 1
+    }
+
+    /// Removes every hook at or above the depth.
+    pub fn truncate(&self, depth: usize) {
+        self.stack.borrow_mut().truncate(depth);
+    }
+}
@@ -0,0 +1,48 @@
+//! Conversions between audits and the errors of other error handling libraries.
+//!
+//! Going into an audit, every context of the other error becomes an audit error holding its message,
+//! and the original error is kept as the innermost audit error showing its root cause.
+//! Going back gives the original error with its backtrace, contexts and root cause,
+//! with every audit error added in the meantime as an extra context on top.
+//!
+//! Errors which did not come from the other library are boxed, they keep their message and sources
+//! but cannot be downcast through the other library anymore.
+//! The children of an audit have no place in the other errors and get dropped.
+//!
+//! The [chain](crate::audit::Audit::chain) yields the root cause of the original error in place of the error holding it,
+//! so [is](crate::audit::Audit::is) and [downcast_ref](crate::audit::Audit::downcast_ref) find it like any other error.
+
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+#[cfg(feature = "anyhow")]
+pub mod anyhow;
+#[cfg(feature = "eyre")]
+pub mod eyre;
+
+/// The message of a context from another error handling library.
+#[derive(Debug)]
+pub struct ContextMessage(pub String);
+
+impl Display for ContextMessage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Error for ContextMessage {}
+
+/// Returns the root cause of the error if it holds the error of another error handling library, else the error itself.
+pub(crate) fn root_cause<'a>(error: &'a (dyn Error + 'static)) -> &'a (dyn Error + 'static) {
+    #[cfg(feature = "anyhow")]
+    if let Some(cause) = error.downcast_ref::<anyhow::AnyhowCause>() {
+        return cause.0.root_cause();
+    }
+
+    #[cfg(feature = "eyre")]
+    if let Some(cause) = error.downcast_ref::<eyre::EyreCause>() {
+        return cause.0.root_cause();
+    }
+
+    error
+}
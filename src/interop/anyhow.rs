@@ -0,0 +1,89 @@
+use std::backtrace::BacktraceStatus;
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+
+use crate::audit::{Audit, AuditSection, AuditSectionEntry};
+use crate::interop::ContextMessage;
+
+/// The anyhow error an audit was created from, it shows the root cause of the error.
+pub struct AnyhowCause(pub anyhow::Error);
+
+impl Display for AnyhowCause {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(self.0.root_cause(), f)
+    }
+}
+
+impl Debug for AnyhowCause {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(self.0.root_cause(), f)
+    }
+}
+
+impl Error for AnyhowCause {}
+
+impl Audit {
+    /// Converts the anyhow error into an audit, every context becomes an audit error.
+    /// If anyhow captured a backtrace it gets added as a section.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anyhow::Context;
+    /// use anyways::audit::Audit;
+    ///
+    /// let error = std::fs::read("./missing").context("Failed to read config").unwrap_err();
+    /// let audit = Audit::from_anyhow(error);
+    /// assert_eq!(audit.errors.len(), 2);
+    /// assert_eq!(audit.errors[0].to_string(), "Failed to read config");
+    /// assert!(audit.into_anyhow().downcast_ref::<std::io::Error>().is_some());
+    /// ```
+    #[track_caller]
+    pub fn from_anyhow(error: anyhow::Error) -> Audit {
+        let mut audit = Audit::new_empty();
+        let backtrace = error.backtrace();
+        if backtrace.status() == BacktraceStatus::Captured {
+            let entries = backtrace.to_string().lines().map(AuditSectionEntry::text).collect();
+            audit.push_section(AuditSection::new("Backtrace (anyhow)", entries));
+        }
+
+        let mut contexts: Vec<String> = error.chain().map(|err| err.to_string()).collect();
+        // The last error is the root cause.
+        contexts.pop();
+
+        audit.push_err(AnyhowCause(error));
+        for context in contexts.into_iter().rev() {
+            audit.push_err(ContextMessage(context));
+        }
+        audit
+    }
+
+    /// Converts the audit into an anyhow error, the audit errors become contexts around the innermost error.
+    pub fn into_anyhow(self) -> anyhow::Error {
+        if self.errors.is_empty() {
+            return anyhow::Error::msg(self.summary());
+        }
+
+        let mut errors = self.errors.into_iter().rev();
+        let innermost = errors.next().unwrap();
+        let (mut error, original) = match innermost.error.downcast::<AnyhowCause>() {
+            Ok(cause) => (cause.0, true),
+            Err(error) => (anyhow::Error::from_boxed(error), false),
+        };
+
+        for err in errors {
+            // The contexts of the original error are still part of it.
+            if original && err.is::<ContextMessage>() {
+                continue;
+            }
+            error = error.context(err.error);
+        }
+        error
+    }
+}
+
+impl From<Audit> for anyhow::Error {
+    fn from(audit: Audit) -> Self {
+        audit.into_anyhow()
+    }
+}
@@ -0,0 +1,102 @@
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+
+use crate::audit::{Audit, Err};
+use crate::interop::ContextMessage;
+
+/// The eyre report an audit was created from, it shows the root cause of the error.
+pub struct EyreCause(pub eyre::Report);
+
+impl Display for EyreCause {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(self.0.root_cause(), f)
+    }
+}
+
+impl Debug for EyreCause {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(self.0.root_cause(), f)
+    }
+}
+
+impl Error for EyreCause {}
+
+impl Audit {
+    /// Converts the eyre report into an audit, every context becomes an audit error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use eyre::WrapErr;
+    /// use anyways::audit::Audit;
+    ///
+    /// let error = std::fs::read("./missing").wrap_err("Failed to read config").unwrap_err();
+    /// let audit = Audit::from_eyre(error);
+    /// assert_eq!(audit.errors.len(), 2);
+    /// assert_eq!(audit.errors[0].to_string(), "Failed to read config");
+    /// assert!(audit.into_eyre().downcast_ref::<std::io::Error>().is_some());
+    /// ```
+    #[track_caller]
+    pub fn from_eyre(error: eyre::Report) -> Audit {
+        let mut audit = Audit::new_empty();
+        let mut contexts: Vec<String> = error.chain().map(|err| err.to_string()).collect();
+        // The last error is the root cause.
+        contexts.pop();
+
+        audit.push_err(EyreCause(error));
+        for context in contexts.into_iter().rev() {
+            audit.push_err(ContextMessage(context));
+        }
+        audit
+    }
+
+    /// Converts the audit into an eyre report, the audit errors become contexts around the innermost error.
+    pub fn into_eyre(self) -> eyre::Report {
+        if self.errors.is_empty() {
+            return eyre::Report::msg(self.summary());
+        }
+
+        let mut errors = self.errors.into_iter().rev();
+        let innermost = errors.next().unwrap();
+        let (mut error, original) = match innermost.error.downcast::<EyreCause>() {
+            Ok(cause) => (cause.0, true),
+            Err(error) => (eyre::Report::new(BoxedError(error)), false),
+        };
+
+        for err in errors {
+            // The contexts of the original error are still part of it.
+            if original && err.is::<ContextMessage>() {
+                continue;
+            }
+            error = error.wrap_err(err.error);
+        }
+        error
+    }
+}
+
+impl From<Audit> for eyre::Report {
+    fn from(audit: Audit) -> Self {
+        audit.into_eyre()
+    }
+}
+
+/// An audit error as a concrete error type, as eyre cannot hold a boxed error.
+struct BoxedError(Box<dyn Err>);
+
+impl Display for BoxedError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl Debug for BoxedError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&self.0, f)
+    }
+}
+
+impl Error for BoxedError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.0.source()
+    }
+}
@@ -0,0 +1,205 @@
+//! Machine readable audits.
+//!
+//! [Audit::to_json] writes the audit as a single line JSON object so it can be used as a JSON line.
+//! Every object has a `version` which gets increased whenever a field is removed or changes its meaning,
+//! new fields can be added without a new version.
+//!
+//! # Schema (version 1)
+//!
+//! ```text
+//! {
+//!   "version": 1,
+//!   "errors": [{
+//!     "label": "E0",                 // The label shown in the backtrace, children use labels like "E0.1"
+//!     "message": "Failed to read",   // The Display output of the error
+//!     "type": "alloc::string::String", // The name of the type the error was created from
+//!     "location": { "file": "src/main.rs", "line": 12, "column": 5 } | null,
+//!     "sources": ["No such file"],   // The messages of Error::source from the outermost to the innermost
+//!     "code": "APP-0042" | null,     // The error code
+//!     "url": "https://docs.example/errors/APP-0042" | null // The documentation link of the code
+//!   }],
+//!   "sections": [{
+//!     "name": "Plugins",
+//!     "entries": [{
+//!       "prefix": null, "prefix_left": "name", "prefix_right": null,
+//!       "text": "loader", "suffix": null, "depth": 0
+//!     }]
+//!   }],
+//!   "backtraces": [{
+//!     "label": "E",                  // The label of the audit the backtrace belongs to, for example "E0.1" for a child
+//!     "frames": [{
+//!       "file": "src/main.rs" | null,
+//!       "line": 12 | null,
+//!       "column": 5 | null,
+//!       "module": "my_app" | null,
+//!       "function": "main",
+//!       "errors": ["E0"]             // The labels of the errors which were added at this frame
+//!     }]
+//!   }],
+//!   "code": "APP-0042" | null        // The first error code of the chain
+//! }
+//! ```
+//!
+//! The custom sections and the frames are taken from the installed [AuditProcessor](crate::processor::AuditProcessor),
+//! so they are filtered and shortened the same way as the formatted audit.
+
+use std::fmt::Write;
+
+use crate::audit::{Audit, AuditSectionEntry};
+use crate::get_audit_processor;
+use crate::processor::AuditNode;
+use crate::style::StyledText;
+
+/// The version of the JSON schema.
+pub const VERSION: u32 = 1;
+
+pub(crate) fn to_json(audit: &Audit) -> String {
+    let processor = get_audit_processor();
+    let nodes = AuditNode::collect(audit);
+
+    let mut out = String::new();
+    write!(out, "{{\"version\":{VERSION},\"errors\":[").unwrap();
+    let mut first = true;
+    for node in &nodes {
+        for (i, err) in node.audit.errors.iter().enumerate() {
+            separate(&mut out, &mut first);
+            out.push_str("{\"label\":");
+            string(&mut out, &node.label(i));
+            out.push_str(",\"message\":");
+            string(&mut out, &err.error.to_string());
+            out.push_str(",\"type\":");
+            string(&mut out, err.type_name);
+            out.push_str(",\"location\":");
+            match &err.location {
+                Some(location) => {
+                    out.push_str("{\"file\":");
+                    string(&mut out, &location.file);
+                    write!(out, ",\"line\":{},\"column\":{}}}", location.line, location.column).unwrap();
+                }
+                None => out.push_str("null"),
+            }
+            out.push_str(",\"sources\":[");
+            let mut source = err.error.source();
+            let mut first_source = true;
+            while let Some(err) = source {
+                separate(&mut out, &mut first_source);
+                string(&mut out, &err.to_string());
+                source = err.source();
+            }
+            let code = node.audit.error_code(i);
+            out.push_str("],\"code\":");
+            optional(&mut out, code);
+            out.push_str(",\"url\":");
+            optional(&mut out, code.and_then(crate::code::url).as_deref());
+            out.push('}');
+        }
+    }
+
+    out.push_str("],\"sections\":[");
+    let mut first = true;
+    for section in &processor.custom_sections(audit) {
+        separate(&mut out, &mut first);
+        out.push_str("{\"name\":");
+        string(&mut out, &section.name);
+        out.push_str(",\"entries\":[");
+        let mut first_entry = true;
+        for entry in &section.entries {
+            separate(&mut out, &mut first_entry);
+            section_entry(&mut out, entry);
+        }
+        out.push_str("]}");
+    }
+
+    out.push_str("],\"backtraces\":[");
+    let mut first = true;
+    for node in &nodes {
+        if node.holds_only_children() {
+            continue;
+        }
+
+        separate(&mut out, &mut first);
+        out.push_str("{\"label\":");
+        string(&mut out, &node.id);
+        out.push_str(",\"frames\":[");
+        let mut first_frame = true;
+        for frame in processor.frames(node) {
+            separate(&mut out, &mut first_frame);
+            out.push_str("{\"file\":");
+            optional(&mut out, frame.file.as_deref());
+            out.push_str(",\"line\":");
+            number(&mut out, frame.line);
+            out.push_str(",\"column\":");
+            number(&mut out, frame.column);
+            out.push_str(",\"module\":");
+            optional(&mut out, frame.module.as_deref());
+            out.push_str(",\"function\":");
+            string(&mut out, &frame.function);
+            out.push_str(",\"errors\":[");
+            let mut first_error = true;
+            for label in &frame.errors {
+                separate(&mut out, &mut first_error);
+                string(&mut out, label);
+            }
+            out.push_str("]}");
+        }
+        out.push_str("]}");
+    }
+    out.push_str("],\"code\":");
+    optional(&mut out, audit.get_code());
+    out.push('}');
+    out
+}
+
+fn section_entry(out: &mut String, entry: &AuditSectionEntry) {
+    let text = |text: &Option<StyledText>| text.as_ref().map(|v| v.to_string());
+
+    out.push_str("{\"prefix\":");
+    optional(out, text(&entry.prefix).as_deref());
+    out.push_str(",\"prefix_left\":");
+    optional(out, text(&entry.prefix_left).as_deref());
+    out.push_str(",\"prefix_right\":");
+    optional(out, text(&entry.prefix_right).as_deref());
+    out.push_str(",\"text\":");
+    string(out, &entry.text.to_string());
+    out.push_str(",\"suffix\":");
+    optional(out, text(&entry.suffix).as_deref());
+    write!(out, ",\"depth\":{}}}", entry.depth).unwrap();
+}
+
+fn separate(out: &mut String, first: &mut bool) {
+    if !*first {
+        out.push(',');
+    }
+    *first = false;
+}
+
+fn optional(out: &mut String, value: Option<&str>) {
+    match value {
+        Some(value) => string(out, value),
+        None => out.push_str("null"),
+    }
+}
+
+fn number(out: &mut String, value: Option<u32>) {
+    match value {
+        Some(value) => write!(out, "{value}").unwrap(),
+        None => out.push_str("null"),
+    }
+}
+
+/// Writes the value as a JSON string.
+fn string(out: &mut String, value: &str) {
+    out.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            ch if ch.is_control() => write!(out, "\\u{:04x}", ch as u32).unwrap(),
+            ch => out.push(ch),
+        }
+    }
+    out.push('"');
+}
@@ -11,72 +11,921 @@
 //! 1. Audit gets made
 //! 2. The AuditProcessor removes useless information and makes the information more digestible
 //! 3. The AuditFormatter formats the audit sections to the output.
-use owo_colors::{Style};
+//!
+//! ## Features
+//! - `send` and `sync` require every audit error to be `Send` and `Sync`, which makes [Audit] `Send` and `Sync`.
+//! - `tracing` captures the tracing span trace of every audit.
+//! - `anyhow` and `eyre` add conversions from and to their errors, see the `interop` module.
+//!   Their errors are always `Send` and `Sync`, so these features turn on `send` and `sync` as well.
+//! - `regex` adds the regex variant of [ProcessingValueMatcher](processor::entry::ProcessingValueMatcher).
+//!
+//! Cargo unifies features across a workspace, so once any crate turns on `anyhow`, `eyre`, `send` or `sync`,
+//! every crate in the build gets the stricter bound and can no longer add errors which are not `Send` or `Sync`.
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::marker::PhantomData;
+use std::sync::{Arc, OnceLock};
+
 use crate::audit::Audit;
 use crate::formatter::{AnywaysAuditFormatter, AuditFormatter};
+use crate::hook::{Hook, ScopedHooks};
 use crate::processor::{AnywaysAuditProcessorBuilder, AuditProcessor};
 
+#[macro_use]
+mod macros;
+
 pub mod audit;
+pub mod code;
+pub mod exit;
 pub mod ext;
 pub mod formatter;
+#[cfg(any(feature = "anyhow", feature = "eyre"))]
+pub mod interop;
+pub mod json;
+pub mod panic;
 pub mod processor;
+pub mod std_error;
+pub mod style;
 mod align;
+mod hook;
 
 pub type Result<T, E = Audit> = std::result::Result<T, E>;
 
-static mut AUDIT_FORMATTER: Option<Box<dyn AuditFormatter>> = None;
-static mut AUDIT_PROCESSOR: Option<Box<dyn AuditProcessor>> = None;
+static AUDIT_FORMATTER: Hook<dyn AuditFormatter> = Hook::new();
+static AUDIT_PROCESSOR: Hook<dyn AuditProcessor> = Hook::new();
+
+thread_local! {
+    static SCOPED_AUDIT_FORMATTER: ScopedHooks<dyn AuditFormatter> = const { ScopedHooks::new() };
+    static SCOPED_AUDIT_PROCESSOR: ScopedHooks<dyn AuditProcessor> = const { ScopedHooks::new() };
+}
+
+/// Sets the global AuditFormatter, replacing and returning the previously installed one.
+pub fn set_audit_formatter(formatter: impl AuditFormatter + 'static) -> Option<Arc<dyn AuditFormatter>> {
+    AUDIT_FORMATTER.set(Arc::new(formatter))
+}
 
-pub fn set_audit_formatter(formatter: impl AuditFormatter + 'static) {
-    unsafe {
-        AUDIT_FORMATTER = Some(Box::new(formatter));
+/// Sets the global AuditProcessor, replacing and returning the previously installed one.
+pub fn set_audit_processor(processor: impl AuditProcessor + 'static) -> Option<Arc<dyn AuditProcessor>> {
+    AUDIT_PROCESSOR.set(Arc::new(processor))
+}
+
+/// Installs the global AuditFormatter if no formatter has been installed yet.
+///
+/// # Examples
+///
+/// ```
+/// use anyways::formatter::AnywaysAuditFormatter;
+///
+/// anyways::install_audit_formatter(AnywaysAuditFormatter::default()).unwrap();
+/// assert!(anyways::install_audit_formatter(AnywaysAuditFormatter::default()).is_err());
+/// ```
+pub fn install_audit_formatter(formatter: impl AuditFormatter + 'static) -> Result<(), HookInstalledError> {
+    AUDIT_FORMATTER
+        .install(Arc::new(formatter))
+        .map_err(|_| HookInstalledError { hook: "AuditFormatter" })
+}
+
+/// Installs the global AuditProcessor if no processor has been installed yet.
+pub fn install_audit_processor(processor: impl AuditProcessor + 'static) -> Result<(), HookInstalledError> {
+    AUDIT_PROCESSOR
+        .install(Arc::new(processor))
+        .map_err(|_| HookInstalledError { hook: "AuditProcessor" })
+}
+
+/// Removes the global AuditFormatter, making audits use the default formatter again.
+pub fn take_audit_formatter() -> Option<Arc<dyn AuditFormatter>> {
+    AUDIT_FORMATTER.take()
+}
+
+/// Removes the global AuditProcessor, making audits use the default processor again.
+pub fn take_audit_processor() -> Option<Arc<dyn AuditProcessor>> {
+    AUDIT_PROCESSOR.take()
+}
+
+/// Returns true if a global AuditFormatter has been installed.
+pub fn has_audit_formatter() -> bool {
+    AUDIT_FORMATTER.is_set()
+}
+
+/// Returns true if a global AuditProcessor has been installed.
+pub fn has_audit_processor() -> bool {
+    AUDIT_PROCESSOR.is_set()
+}
+
+/// Replaces the panic hook so panics get reported like audits, through the AuditProcessor and AuditFormatter of the panicking thread.
+///
+/// The audit holds a [PanicError](panic::PanicError) with the panic message at the location of the panic.
+///
+/// # Examples
+///
+/// ```
+/// anyways::install_panic_hook();
+/// ```
+pub fn install_panic_hook() {
+    std::panic::set_hook(Box::new(|info| eprintln!("{:?}", panic::audit(info))));
+}
+
+/// Overrides the AuditFormatter for the current thread until the returned guard is dropped.
+///
+/// # Examples
+///
+/// ```
+/// use anyways::audit::Audit;
+/// use anyways::formatter::AnywaysAuditFormatter;
+///
+/// let guard = anyways::override_audit_formatter(AnywaysAuditFormatter {
+///     simple_section: true,
+///     ..AnywaysAuditFormatter::default()
+/// });
+/// println!("{}", Audit::new("Printed with simple sections"));
+/// drop(guard);
+/// ```
+pub fn override_audit_formatter(formatter: impl AuditFormatter + 'static) -> HookGuard {
+    HookGuard {
+        depth: SCOPED_AUDIT_FORMATTER.with(|hooks| hooks.push(Arc::new(formatter))),
+        restore: |depth| {
+            let _ = SCOPED_AUDIT_FORMATTER.try_with(|hooks| hooks.truncate(depth));
+        },
+        _thread: PhantomData,
     }
 }
 
-pub fn set_audit_processor(processor: impl AuditProcessor + 'static) {
-    unsafe {
-        AUDIT_PROCESSOR = Some(Box::new(processor));
+/// Overrides the AuditProcessor for the current thread until the returned guard is dropped.
+pub fn override_audit_processor(processor: impl AuditProcessor + 'static) -> HookGuard {
+    HookGuard {
+        depth: SCOPED_AUDIT_PROCESSOR.with(|hooks| hooks.push(Arc::new(processor))),
+        restore: |depth| {
+            let _ = SCOPED_AUDIT_PROCESSOR.try_with(|hooks| hooks.truncate(depth));
+        },
+        _thread: PhantomData,
     }
 }
 
-pub fn get_audit_formatter() -> &'static dyn AuditFormatter {
-    unsafe {
-        if AUDIT_FORMATTER.is_none() {
-            set_audit_formatter(AnywaysAuditFormatter::default());
-        }
-        AUDIT_FORMATTER.as_deref().unwrap()
+/// Runs the function with the AuditFormatter overridden for the current thread.
+///
+/// # Examples
+///
+/// ```
+/// use anyways::audit::Audit;
+/// use anyways::formatter::AnywaysAuditFormatter;
+///
+/// let audit = Audit::new("Failed to start");
+/// let plain = anyways::with_audit_formatter(
+///     AnywaysAuditFormatter {
+///         simple_section: true,
+///         ..AnywaysAuditFormatter::default()
+///     },
+///     || audit.to_string(),
+/// );
+/// ```
+pub fn with_audit_formatter<R>(formatter: impl AuditFormatter + 'static, func: impl FnOnce() -> R) -> R {
+    let _guard = override_audit_formatter(formatter);
+    func()
+}
+
+/// Runs the function with the AuditProcessor overridden for the current thread.
+pub fn with_audit_processor<R>(processor: impl AuditProcessor + 'static, func: impl FnOnce() -> R) -> R {
+    let _guard = override_audit_processor(processor);
+    func()
+}
+
+/// Gets the AuditFormatter for the current thread.
+///
+/// This is the innermost thread-local override, else the installed global formatter,
+/// else the default [AnywaysAuditFormatter].
+pub fn get_audit_formatter() -> Arc<dyn AuditFormatter> {
+    static DEFAULT: OnceLock<Arc<dyn AuditFormatter>> = OnceLock::new();
+    SCOPED_AUDIT_FORMATTER
+        .try_with(|hooks| hooks.last())
+        .ok()
+        .flatten()
+        .or_else(|| AUDIT_FORMATTER.get())
+        .unwrap_or_else(|| {
+            DEFAULT
+                .get_or_init(|| Arc::new(AnywaysAuditFormatter::default()))
+                .clone()
+        })
+}
+
+/// Gets the AuditProcessor for the current thread.
+///
+/// This is the innermost thread-local override, else the installed global processor,
+/// else the default [AnywaysAuditProcessor](processor::AnywaysAuditProcessor).
+pub fn get_audit_processor() -> Arc<dyn AuditProcessor> {
+    static DEFAULT: OnceLock<Arc<dyn AuditProcessor>> = OnceLock::new();
+    SCOPED_AUDIT_PROCESSOR
+        .try_with(|hooks| hooks.last())
+        .ok()
+        .flatten()
+        .or_else(|| AUDIT_PROCESSOR.get())
+        .unwrap_or_else(|| {
+            DEFAULT
+                .get_or_init(|| Arc::new(AnywaysAuditProcessorBuilder::default().build()))
+                .clone()
+        })
+}
+
+/// Restores the previous thread-local hook when dropped.
+///
+/// Returned by [override_audit_formatter] and [override_audit_processor].
+/// Dropping a guard also removes every override made after it on the same thread.
+#[must_use = "the override is removed as soon as the guard is dropped"]
+pub struct HookGuard {
+    depth: usize,
+    restore: fn(usize),
+    // The override lives in a thread local, so the guard must stay on its thread.
+    _thread: PhantomData<*const ()>,
+}
+
+impl Drop for HookGuard {
+    fn drop(&mut self) {
+        (self.restore)(self.depth);
     }
 }
 
-pub fn get_audit_processor() -> &'static dyn AuditProcessor {
-    unsafe {
-        if AUDIT_PROCESSOR.is_none() {
-            set_audit_processor(AnywaysAuditProcessorBuilder::default().build());
-        }
-        AUDIT_PROCESSOR.as_deref().unwrap()
+/// Returned when installing a hook while another one is already installed.
+#[derive(Debug, Clone)]
+pub struct HookInstalledError {
+    hook: &'static str,
+}
+
+impl Display for HookInstalledError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "An {} is already installed", self.hook)
     }
 }
 
+impl Error for HookInstalledError {}
+
 #[cfg(test)]
 mod tests {
-    use std::fs::File;
+    use std::error::Error;
+    use std::fmt::{Display, Formatter};
+    use std::{io, thread};
+    use std::sync::{Arc, Mutex};
     use owo_colors::{AnsiColors, DynColors};
-    use crate::audit::{AuditSection, AuditSectionEntry};
+    use crate::audit::{Audit, AuditError, AuditSection, AuditSectionEntry, AuditSectionKind};
+    use crate::code;
 
     use crate::ext::AuditExt;
+    use crate::formatter::{get_length, AnywaysAuditFormatter, AuditFormatter, Charset, ColorChoice};
+    use crate::formatter::html::HtmlAuditFormatter;
+    use crate::formatter::markdown::MarkdownAuditFormatter;
+    use crate::processor::{AnywaysAuditProcessorBuilder, AuditNode, AuditProcessor};
+    use crate::processor::entry::{glob_matches, ProcessingValueMatcher};
+    use crate::style::{Span, SpanStyle};
     use crate::Result;
 
     #[test]
-    fn thigns() -> Result<()> {
-        read_plugin_before().wrap_err("Failed to read plugin").wrap(|audit| {
+    fn thigns() {
+        let result = read_plugin_before().wrap_err("Failed to read plugin").wrap(|audit| {
             audit.custom_sections.push(AuditSection {
                 name: "Dogs".to_string(),
                 color: DynColors::Ansi(AnsiColors::BrightBlue),
+                kind: AuditSectionKind::Custom,
                 entries: vec![
                     AuditSectionEntry::text("Sheril".to_string())
-                ]
+                ],
+                footer: None,
             })
-        })
+        });
+
+        let report = format!("{:?}", result.unwrap_err());
+        assert!(report.contains("Dogs"), "{report}");
+        assert!(report.contains("Sheril"), "{report}");
+    }
+
+    #[test]
+    fn hooks_across_threads() {
+        // Takes the global formatter again even if an assertion fails, so it does not leak into other tests.
+        struct FormatterGuard;
+
+        impl Drop for FormatterGuard {
+            fn drop(&mut self) {
+                crate::take_audit_formatter();
+            }
+        }
+
+        let _guard = FormatterGuard;
+        let threads: Vec<_> = (0..8)
+            .map(|i| {
+                thread::spawn(move || {
+                    if i % 2 == 0 {
+                        crate::set_audit_formatter(AnywaysAuditFormatter::default());
+                    }
+                    Audit::new("Thread failed").to_string()
+                })
+            })
+            .collect();
+
+        for thread in threads {
+            assert!(thread.join().unwrap().contains("Thread failed"));
+        }
+        assert!(crate::has_audit_formatter());
+        assert!(crate::install_audit_formatter(AnywaysAuditFormatter::default()).is_err());
+    }
+
+    struct NameFormatter;
+
+    impl AuditFormatter for NameFormatter {
+        fn format(&self, f: &mut std::fmt::Formatter, sections: &[AuditSection]) -> std::fmt::Result {
+            for section in sections {
+                write!(f, "[{}]", section.name)?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn scoped_formatter() {
+        let audit = Audit::new("Scoped");
+        let scoped = crate::with_audit_formatter(NameFormatter, || {
+            // Snippets would show this source line, which contains the scoped output.
+            let other_thread = thread::spawn(|| {
+                let processor = AnywaysAuditProcessorBuilder {
+                    snippet_error_frames: false,
+                    ..AnywaysAuditProcessorBuilder::default()
+                };
+                crate::with_audit_processor(processor.build(), || Audit::new("Scoped").to_string())
+            });
+            assert!(!other_thread.join().unwrap().contains("[Errors][Backtrace]"));
+            audit.to_string()
+        });
+
+        assert_eq!(scoped, "\n[Errors][Backtrace]");
+        assert_ne!(audit.to_string(), scoped);
+    }
+
+    #[derive(Debug)]
+    struct LibraryError(io::Error);
+
+    impl Display for LibraryError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            f.write_str("Library failed")
+        }
+    }
+
+    impl Error for LibraryError {
+        fn source(&self) -> Option<&(dyn Error + 'static)> {
+            Some(&self.0)
+        }
+    }
+
+    #[test]
+    fn chain() {
+        let error = LibraryError(io::Error::new(io::ErrorKind::NotFound, "Missing"));
+        let audit = Err::<(), _>(error).wrap_err("Failed to load").unwrap_err();
+
+        let chain: Vec<String> = audit.chain().map(|err| err.to_string()).collect();
+        assert_eq!(chain, ["Failed to load", "Library failed", "Missing"]);
+        assert_eq!(audit.root_cause().unwrap().to_string(), "Missing");
+        assert!(audit.is::<io::Error>());
+        assert_eq!(audit.downcast_ref::<io::Error>().unwrap().kind(), io::ErrorKind::NotFound);
+
+        let audit = audit.downcast::<io::Error>().unwrap_err();
+        let error = audit.downcast::<LibraryError>().unwrap();
+        assert_eq!(error.0.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn error_sources() {
+        let error = LibraryError(io::Error::new(io::ErrorKind::NotFound, "Missing"));
+        let audit = Err::<(), _>(error).wrap_err("Failed to load").unwrap_err();
+
+        let processor = AnywaysAuditProcessorBuilder::default().build();
+        let section = processor.create_error_section(&audit);
+        let texts: Vec<String> = section.entries.iter().map(|entry| entry.text.to_string()).collect();
+        assert_eq!(texts, ["Failed to load", "Library failed", "↳ Missing"]);
+
+        let processor = AnywaysAuditProcessorBuilder {
+            error_sources: false,
+            ..AnywaysAuditProcessorBuilder::default()
+        }.build();
+        assert_eq!(processor.create_error_section(&audit).entries.len(), 2);
+    }
+
+    fn check_size(size: usize) -> Result<usize> {
+        ensure!(size != 0);
+        ensure!(size < 16, "Size {size} is too big");
+        if size == 13 {
+            bail!("Unlucky size");
+        }
+        Ok(size)
+    }
+
+    #[test]
+    fn macros() {
+        let message = |size| check_size(size).unwrap_err().errors[0].to_string();
+        assert_eq!(message(0), "Condition failed: `size != 0`");
+        assert_eq!(message(20), "Size 20 is too big");
+        assert_eq!(message(13), "Unlucky size");
+        assert_eq!(check_size(3).unwrap(), 3);
+
+        let audit = audit!(io::Error::new(io::ErrorKind::NotFound, "Missing"));
+        assert!(audit.is::<io::Error>());
+    }
+
+    #[test]
+    fn caller_location() {
+        let audit = None::<()>.wrap_err("Missing").unwrap_err();
+        let location = audit.errors[0].location.as_ref().unwrap();
+        assert_eq!((location.file.as_ref(), location.line), (file!(), line!() - 2));
+
+        let audit = audit!("Here");
+        assert_eq!(audit.errors[0].location.as_ref().unwrap().line, line!() - 1);
+
+        let section = AuditSection::new("Dogs", vec![]);
+        let audit = Err::<(), _>(io::Error::other("Missing")).wrap_section(section).unwrap_err();
+        let location = audit.errors[0].location.as_ref().unwrap();
+        assert_eq!((location.file.as_ref(), location.line), (file!(), line!() - 2));
+    }
+
+    #[test]
+    fn join() {
+        let mut audit = Audit::join([
+            Audit::new("Missing name"),
+            Err::<(), _>(io::Error::new(io::ErrorKind::NotFound, "Missing"))
+                .wrap_err("Missing version")
+                .unwrap_err(),
+        ]);
+        audit.push_err("Invalid manifest");
+
+        let processor = AnywaysAuditProcessorBuilder::default().build();
+        let section = processor.create_error_section(&audit);
+        let labels: Vec<String> = section
+            .entries
+            .iter()
+            .map(|entry| format!("{} {} {}", entry.prefix_left.as_ref().unwrap(), entry.depth, entry.text))
+            .collect();
+        assert_eq!(labels, [
+            "E0 0 Invalid manifest",
+            "E0.1 1 Missing name",
+            "E0.2 1 Missing version",
+            "E0.2.1 1 Missing",
+        ]);
+
+        let names: Vec<String> = processor.process(&audit).into_iter().map(|section| section.name).collect();
+        assert_eq!(names, ["Errors", "Backtrace", "Backtrace E0.1", "Backtrace E0.2"]);
+
+        assert_eq!(audit.chain().count(), 4);
+        assert!(audit.downcast::<io::Error>().is_ok());
+
+        let mut child = Audit::new("Missing version");
+        child.push_child(Audit::new("Missing major"));
+        let audit = Audit::join([Audit::new("Missing name"), child]);
+        let labels: Vec<String> = processor
+            .create_error_section(&audit)
+            .entries
+            .iter()
+            .map(|entry| entry.prefix_left.as_ref().unwrap().to_string())
+            .collect();
+        assert_eq!(labels, ["E0.1", "E0.2", "E0.2.1"]);
+
+        let names: Vec<String> = processor.process(&audit).into_iter().map(|section| section.name).collect();
+        assert_eq!(names, ["Errors", "Backtrace E0.1", "Backtrace E0.2", "Backtrace E0.2.1"]);
+    }
+
+    #[test]
+    fn styled_spans() {
+        let audit = read_plugin_before().unwrap_err();
+        let sections = AnywaysAuditProcessorBuilder::default().build().process(&audit);
+        let spans: Vec<&Span> = sections
+            .iter()
+            .flat_map(|section| &section.entries)
+            .flat_map(|entry| [entry.prefix.as_ref(), entry.prefix_left.as_ref(), entry.prefix_right.as_ref(), Some(&entry.text)])
+            .flatten()
+            .flat_map(|text| &text.spans)
+            .collect();
+
+        assert!(spans.iter().any(|span| span.style == SpanStyle::Replaced));
+        assert!(spans.iter().any(|span| span.style == SpanStyle::ErrorLabel && span.text == "E1"));
+        assert!(spans.iter().all(|span| !span.text.contains(['\x1b', '\u{E000}', '\u{E001}'])));
+    }
+
+    #[test]
+    fn plain_output() {
+        let plain = AnywaysAuditFormatter {
+            color: ColorChoice::Never,
+            charset: Charset::Ascii,
+            ..AnywaysAuditFormatter::default()
+        };
+        let output = crate::with_audit_formatter(plain, || read_plugin_before().unwrap_err().to_string());
+        assert!(output.is_ascii());
+        assert!(output.contains("+-- Errors -"));
+
+        let colored = AnywaysAuditFormatter {
+            color: ColorChoice::Always,
+            ..AnywaysAuditFormatter::default()
+        };
+        let output = crate::with_audit_formatter(colored, || Audit::new("Colored").to_string());
+        assert!(output.contains('\x1b'));
+    }
+
+    #[test]
+    fn configured_width() {
+        let narrow = AnywaysAuditFormatter {
+            width: 60,
+            color: ColorChoice::Never,
+            charset: Charset::Ascii,
+            ..AnywaysAuditFormatter::default()
+        };
+        let output = crate::with_audit_formatter(narrow, || read_plugin_before().unwrap_err().to_string());
+        assert!(output.lines().skip(1).all(|line| line.len() == 60), "{output}");
+    }
+
+    #[test]
+    fn wrapping() {
+        let mut audit = Audit::new("プラグインを読み込めませんでした。ファイルが存在しないか、アクセスする権限がありません 🦀🦀🦀");
+        audit.push_err("Failed to load very::long::module::path::that::does::not::fit::into::a::single::line::of::the::box");
+        for color in [ColorChoice::Never, ColorChoice::Always] {
+            let narrow = AnywaysAuditFormatter {
+                width: 60,
+                color,
+                ..AnywaysAuditFormatter::default()
+            };
+            let output = crate::with_audit_formatter(narrow, || audit.to_string());
+            assert!(output.lines().skip(1).all(|line| get_length(line) == 60), "{output}");
+            if color == ColorChoice::Never {
+                assert!(output.contains("\n|               that::does::not::fit::into::a::single::    |"), "{output}");
+            }
+        }
+    }
+
+    #[test]
+    fn markdown() {
+        let output = crate::with_audit_formatter(MarkdownAuditFormatter::default(), || {
+            read_plugin_before().unwrap_err().to_string()
+        });
+
+        assert!(output.contains("### Errors\n\n1. `E0` Failed to find your mom being gay\n1. `E1` No such file"), "{output}");
+        assert!(output.contains("<details>\n<summary><code>src/lib.rs</code></summary>\n\n```\n"), "{output}");
+    }
+
+    #[test]
+    fn html() {
+        let output = crate::with_audit_formatter(HtmlAuditFormatter::default(), || {
+            read_plugin_before().unwrap_err().to_string()
+        });
+
+        assert!(output.contains("<li id=\"error-E0\" style=\"--depth: 0\"><a class=\"label\" href=\"#frame-E0\">E0</a>"), "{output}");
+        assert!(output.contains("<tr class=\"error\"><td class=\"labels\"><a id=\"frame-E0\" class=\"label\" href=\"#error-E0\">E0</a>"), "{output}");
+        assert!(output.contains("<details class=\"file\" open>\n<summary>src/lib.rs</summary>"), "{output}");
+        assert!(output.contains("<details class=\"file external\">"), "{output}");
+    }
+
+    #[test]
+    fn json() {
+        let json = read_plugin_before().unwrap_err().to_json();
+
+        assert!(json.starts_with("{\"version\":1,\"errors\":[{\"label\":\"E0\",\"message\":\"Failed to find your mom being gay\",\"type\":\"&str\",\"location\":{\"file\":\"src/lib.rs\""), "{json}");
+        assert!(json.contains("\"type\":\"std::io::error::Error\""), "{json}");
+        assert!(json.contains("\"file\":\"src/lib.rs\""), "{json}");
+        assert!(json.contains("\"errors\":[\"E0\",\"E1\"]"), "{json}");
+        assert!(json.contains("\"module\":\"anyways\",\"function\":\"tests::read_plugin_before\""), "{json}");
+        assert!(!json.contains('\n'));
+    }
+
+    #[test]
+    fn summary() {
+        let audit = read_plugin_before().unwrap_err();
+
+        let summary = audit.summary();
+        assert!(summary.starts_with("Failed to find your mom being gay: No such file"), "{summary}");
+        assert!(summary.ends_with(")") && summary.contains(" (at src/lib.rs:"), "{summary}");
+        assert_eq!(format!("{audit:#}"), summary);
+    }
+
+    #[test]
+    fn snippets() {
+        let audit = read_plugin_before().unwrap_err();
+        let sections = AnywaysAuditProcessorBuilder::default().build().process(&audit);
+        let backtrace = sections.iter().find(|section| section.kind == AuditSectionKind::Backtrace).unwrap();
+
+        let highlighted: Vec<String> = backtrace
+            .entries
+            .iter()
+            .flat_map(|entry| &entry.text.spans)
+            .filter(|span| span.style == SpanStyle::Highlight)
+            .map(|span| span.text.clone())
+            .collect();
+        assert_eq!(highlighted, ["wrap_err", "^^^^^^^^"]);
+
+        let sections = AnywaysAuditProcessorBuilder {
+            snippet_error_frames: false,
+            ..AnywaysAuditProcessorBuilder::default()
+        }
+        .build()
+        .process(&audit);
+        assert!(sections.iter().flat_map(|section| &section.entries).all(|entry| entry.text.spans.iter().all(|span| span.style != SpanStyle::Code)));
+    }
+
+    #[derive(Clone, Default)]
+    struct CaptureFormatter(Arc<Mutex<Vec<String>>>);
+
+    impl AuditFormatter for CaptureFormatter {
+        fn format(&self, _: &mut std::fmt::Formatter, sections: &[AuditSection]) -> std::fmt::Result {
+            let errors = sections.iter().filter(|section| section.kind == AuditSectionKind::Errors);
+            let mut captured = self.0.lock().unwrap();
+            captured.extend(errors.flat_map(|section| &section.entries).map(|entry| entry.text.to_string()));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn panic_hook() {
+        let formatter = CaptureFormatter::default();
+        let captured = Arc::clone(&formatter.0);
+        let previous = std::panic::take_hook();
+        crate::install_panic_hook();
+        let result = thread::Builder::new()
+            .name("plugin".to_string())
+            .spawn(|| {
+                let _guard = crate::override_audit_formatter(formatter);
+                panic!("Plugin {} crashed", 3);
+            })
+            .unwrap()
+            .join();
+        std::panic::set_hook(previous);
+
+        assert!(result.is_err());
+        assert_eq!(captured.lock().unwrap().as_slice(), ["thread 'plugin' panicked: Plugin 3 crashed"]);
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn span_trace() {
+        use tracing_error::ErrorLayer;
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let subscriber = tracing_subscriber::registry().with(ErrorLayer::default());
+        let audit = tracing::subscriber::with_default(subscriber, || {
+            let _request = tracing::info_span!("request", id = 3).entered();
+            let _plugin = tracing::info_span!("load_plugin").entered();
+            Audit::new("Failed to load plugin")
+        });
+
+        let sections = AnywaysAuditProcessorBuilder::default().build().process(&audit);
+        let section = sections.iter().find(|section| section.name == "Span trace").unwrap();
+        let spans: Vec<String> = section.entries.iter().filter(|entry| entry.depth == 0).map(|entry| entry.text.to_string()).collect();
+        assert_eq!(spans, ["load_plugin", "request id=3"]);
+        assert!(section.entries[1].text.to_string().starts_with("at src/lib.rs:"));
+    }
+
+    #[cfg(feature = "anyhow")]
+    #[test]
+    fn anyhow() {
+        use anyhow::Context;
+        use std::fs::File;
+
+        let error = File::open("./missing").context("Failed to open config").unwrap_err();
+        let audit = Err::<(), _>(Audit::from_anyhow(error)).wrap_err("Failed to start").unwrap_err();
+        let messages: Vec<String> = audit.errors.iter().map(|err| err.to_string()).collect();
+        assert_eq!(messages[0], "Failed to start");
+        assert_eq!(messages[1], "Failed to open config");
+        assert!(messages[2].starts_with("No such file"));
+        assert!(audit.is::<io::Error>());
+        assert!(audit.downcast_ref::<io::Error>().is_some());
+
+        let error = anyhow::Error::from(audit);
+        let messages: Vec<String> = error.chain().map(|err| err.to_string()).collect();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0], "Failed to start");
+        assert!(error.downcast_ref::<io::Error>().is_some());
+
+        let error = anyhow::Error::from(Audit::new(LibraryError(io::Error::other("Disk full"))));
+        assert_eq!(error.to_string(), "Library failed");
+        assert!(error.chain().any(|err| err.is::<io::Error>()));
+    }
+
+    #[cfg(feature = "eyre")]
+    #[test]
+    fn eyre() {
+        use std::fs::File;
+
+        let error = eyre::WrapErr::wrap_err(File::open("./missing"), "Failed to open config").unwrap_err();
+        let audit = Err::<(), _>(Audit::from_eyre(error)).wrap_err("Failed to start").unwrap_err();
+        assert_eq!(audit.errors.len(), 3);
+        assert!(audit.is::<io::Error>());
+        assert!(audit.downcast_ref::<io::Error>().is_some());
+
+        let error = eyre::Report::from(audit);
+        let messages: Vec<String> = error.chain().map(|err| err.to_string()).collect();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[1], "Failed to open config");
+        assert!(error.downcast_ref::<io::Error>().is_some());
+    }
+
+    #[test]
+    fn std_error() {
+        fn library() -> std::result::Result<(), Box<dyn crate::audit::Err>> {
+            read_plugin_before().map_err(Audit::into_boxed_error)
+        }
+
+        let error = library().unwrap_err();
+        assert_eq!(error.to_string(), "Failed to find your mom being gay");
+        assert!(error.source().unwrap().to_string().starts_with("No such file"));
+
+        let result: Result<()> = library().wrap_err("Failed to load");
+        let audit = result.unwrap_err();
+        assert_eq!(audit.errors.len(), 3);
+        assert!(audit.errors[0].location.is_some());
+        assert!(audit.is::<io::Error>());
+    }
+
+    #[cfg(all(feature = "send", feature = "sync"))]
+    #[test]
+    fn io_error() {
+        fn library() -> io::Result<()> {
+            read_plugin_before().map_err(Audit::into_io_error)
+        }
+
+        let error = library().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::Other);
+        let audit = Audit::from(error);
+        assert_eq!(audit.errors.len(), 2);
+        assert!(audit.downcast_ref::<io::Error>().is_some());
+    }
+
+    #[derive(Debug)]
+    struct ConfigError;
+
+    impl Display for ConfigError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            f.write_str("Invalid config")
+        }
+    }
+
+    impl Error for ConfigError {}
+
+    #[test]
+    fn exit_codes() {
+        use std::process::{ExitCode, Termination};
+        use crate::exit::Report;
+
+        crate::exit::register_exit_code::<ConfigError>(78);
+        let audit = Err::<(), _>(ConfigError).wrap_err("Failed to start").unwrap_err();
+        assert_eq!(audit.get_exit_code(), 78);
+        assert_eq!(Audit::new("Failed").get_exit_code(), 1);
+
+        let result: Result<()> = Err(ConfigError).wrap_exit_code(3);
+        assert_eq!(result.unwrap_err().get_exit_code(), 3);
+        let result: Result<()> = Err(ConfigError).wrap_exit_code(0);
+        assert_eq!(result.unwrap_err().get_exit_code(), 1);
+
+        let child = Err::<(), _>(Audit::new("Failed")).wrap_exit_code(4).unwrap_err();
+        assert_eq!(Audit::join([child]).get_exit_code(), 4);
+
+        assert_eq!(Report::from(Ok(())).report(), ExitCode::SUCCESS);
+        assert_eq!(Report::from(Audit::new(ConfigError)).report(), ExitCode::from(78));
+    }
+
+    #[test]
+    fn attachments() {
+        let audit = read_plugin_before()
+            .note("The plugin folder is created on the first start")
+            .warning_with(|| format!("{} plugins failed before", 2))
+            .help("Check that the plugin exists")
+            .suggestion("run with --no-plugins")
+            .unwrap_err();
+
+        let sections = AnywaysAuditProcessorBuilder::default().build().process(&audit);
+        let names: Vec<&str> = sections.iter().map(|section| section.name.as_str()).collect();
+        assert_eq!(names, ["Errors", "Help", "Note", "Warning", "Backtrace"]);
+
+        let help: Vec<String> = sections[1].entries.iter().map(|entry| entry.text.to_string()).collect();
+        assert_eq!(help, ["Check that the plugin exists", "try run with --no-plugins"]);
+        assert_eq!(sections[3].entries[0].text.to_string(), "2 plugins failed before");
+
+        let ok: Result<()> = Ok(());
+        assert!(ok.note_with(|| -> String { unreachable!() }).is_ok());
+    }
+
+    #[test]
+    fn error_codes() {
+        let audit = read_plugin_before()
+            .wrap_code("IO-0001")
+            .wrap_err(AuditError::from("Failed to load plugins").with_code("APP-0042"))
+            .unwrap_err();
+        assert_eq!(audit.get_code(), Some("APP-0042"));
+        assert!(audit.summary().starts_with("[APP-0042] Failed to load plugins: "));
+
+        let previous = code::take_url_template();
+        code::set_url_template("https://docs.example/errors/{code}");
+        let sections = AnywaysAuditProcessorBuilder::default().build().process(&audit);
+        let json = audit.to_json();
+        code::take_url_template();
+
+        let errors: Vec<String> = sections[0].entries.iter().map(|entry| entry.text.to_string()).collect();
+        assert_eq!(errors[0], "[APP-0042] Failed to load plugins");
+        assert_eq!(errors[1], "see https://docs.example/errors/APP-0042");
+        assert_eq!(errors[2], "[IO-0001] Failed to find your mom being gay");
+        assert!(json.contains("\"code\":\"IO-0001\",\"url\":\"https://docs.example/errors/IO-0001\""));
+
+        let audit = Err::<(), _>(Audit::join([Audit::new("Failed to load plugin")]))
+            .wrap_code("APP-0043")
+            .unwrap_err();
+        assert_eq!(audit.get_code(), Some("APP-0043"));
+        assert!(audit.summary().starts_with("[APP-0043] "));
+        assert!(audit.to_json().ends_with(",\"code\":\"APP-0043\"}"));
+        let sections = AnywaysAuditProcessorBuilder::default().build().process(&audit);
+        assert_eq!(sections[0].entries[0].text.to_string(), "[APP-0043]");
+
+        let mut audit = Audit::new_empty();
+        audit.set_code("APP-0044");
+        let audit = Err::<(), _>(audit).wrap_err("Failed to start").unwrap_err();
+        let sections = AnywaysAuditProcessorBuilder::default().build().process(&audit);
+        assert_eq!(sections[0].entries[0].text.to_string(), "[APP-0044] Failed to start");
+        assert!(audit.to_json().contains("\"code\":\"APP-0044\",\"url\":null"));
+
+        if let Some(previous) = previous {
+            code::set_url_template(previous);
+        }
+    }
+
+    #[test]
+    fn trimmed_backtrace() {
+        let audit = read_plugin_before().unwrap_err();
+        let node = &AuditNode::collect(&audit)[0];
+
+        let processor = AnywaysAuditProcessorBuilder::default().build();
+        let frames = processor.frames(node);
+        assert_eq!(frames[0].errors, ["E0", "E1"]);
+        assert!(!frames.iter().any(|frame| frame.function.contains("lang_start")));
+
+        let section = processor.create_backtrace_section(&audit, &processor.error_locations(node));
+        assert!(section.footer.unwrap().ends_with(" frames hidden"));
+
+        let untrimmed = AnywaysAuditProcessorBuilder {
+            trim_start: false,
+            trim_end: false,
+            ..AnywaysAuditProcessorBuilder::default()
+        }
+        .build();
+        assert!(untrimmed.frames(node).len() > frames.len());
+    }
+
+    #[test]
+    fn trimmed_backtrace_separate_push() {
+        fn build(line: &mut u32) -> Result<()> {
+            *line = line!() + 1;
+            let mut audit = Audit::new_empty();
+            audit.push_err("Failed to build");
+            Err(audit)
+        }
+
+        let mut line = 0;
+        let audit = build(&mut line).wrap_err("Failed to run").unwrap_err();
+        let frames = AnywaysAuditProcessorBuilder::default().build().frames(&AuditNode::collect(&audit)[0]);
+        assert_eq!(frames[0].file.as_deref(), Some("src/lib.rs"));
+        assert_eq!(frames[0].line, Some(line));
+    }
+
+    #[test]
+    fn trimmed_backtrace_question_mark() {
+        fn open(line: &mut u32) -> Result<()> {
+            *line = line!() + 1;
+            Err(io::Error::other("Missing"))?;
+            Ok(())
+        }
+
+        let mut line = 0;
+        let audit = open(&mut line).unwrap_err();
+        let frames = AnywaysAuditProcessorBuilder::default().build().frames(&AuditNode::collect(&audit)[0]);
+        assert_eq!(frames[0].file.as_deref(), Some("src/lib.rs"));
+        assert_eq!(frames[0].line, Some(line));
+    }
+
+    #[test]
+    fn matchers() {
+        assert!(glob_matches("tokio::runtime::*", "tokio::runtime::park::run"));
+        assert!(glob_matches("*::call_once", "core::ops::function::FnOnce::call_once"));
+        assert!(glob_matches("std::rt::lang_start?", "std::rt::lang_start_"));
+        assert!(!glob_matches("tokio::*", "std::rt::lang_start"));
+
+        let audit = read_plugin_before().unwrap_err();
+        let node = &AuditNode::collect(&audit)[0];
+        let mut processor = AnywaysAuditProcessorBuilder {
+            trim_start: false,
+            trim_end: false,
+            ..AnywaysAuditProcessorBuilder::default()
+        }
+        .build();
+
+        processor.filter.insert(ProcessingValueMatcher::File("*/src/lib.rs".to_string()));
+        processor.filter.insert(ProcessingValueMatcher::custom(|entry| entry.value.get_module() == Some("backtrace")));
+        let frames = processor.frames(node);
+        assert!(!frames.is_empty());
+        assert!(frames.iter().all(|frame| frame.errors.is_empty()));
+        assert!(!frames.iter().any(|frame| frame.module.as_deref() == Some("backtrace")));
+
+        processor.filter.clear();
+        processor.replace.push((ProcessingValueMatcher::Glob("anyways::tests::very_long_module_*".to_string()), "read_plugin".to_string()));
+        #[cfg(feature = "regex")]
+        processor.replace.push((ProcessingValueMatcher::Regex(regex::Regex::new(r"^anyways::tests::matchers").unwrap()), "regex".to_string()));
+        let functions: Vec<String> = processor.frames(node).into_iter().map(|frame| frame.function).collect();
+        assert!(functions.iter().any(|function| function == "read_plugin"), "{functions:?}");
+        #[cfg(feature = "regex")]
+        assert!(functions.iter().any(|function| function.starts_with("regex")), "{functions:?}");
     }
 
     fn read_plugin_before() -> Result<()> {
@@ -0,0 +1,82 @@
+/// Creates an [Audit](crate::audit::Audit) from a format string or an existing error.
+///
+/// The location of the macro call gets recorded on the error the same way [wrap_err](crate::ext::AuditExt::wrap_err) does.
+///
+/// # Examples
+///
+/// ```
+/// use std::fs::File;
+/// use anyways::audit;
+///
+/// let name = "plugin.wasm";
+/// let audit = audit!("Failed to read {name}");
+/// let audit = audit!("Failed to read {}", name);
+/// let audit = audit!(File::open("./hello").unwrap_err());
+/// ```
+#[macro_export]
+macro_rules! audit {
+    ($msg:literal $(,)?) => {
+        $crate::audit::Audit::from(::std::format!($msg))
+    };
+    ($err:expr $(,)?) => {
+        $crate::audit::Audit::from($err)
+    };
+    ($fmt:expr, $($arg:tt)*) => {
+        $crate::audit::Audit::from(::std::format!($fmt, $($arg)*))
+    };
+}
+
+/// Returns early with an [Audit](crate::audit::Audit) created by [audit!](crate::audit!).
+///
+/// # Examples
+///
+/// ```
+/// use anyways::bail;
+///
+/// fn load(name: &str) -> anyways::Result<()> {
+///     if name.is_empty() {
+///         bail!("The plugin name is empty");
+///     }
+///     Ok(())
+/// }
+///
+/// assert!(load("").is_err());
+/// ```
+#[macro_export]
+macro_rules! bail {
+    ($($arg:tt)+) => {
+        return ::std::result::Result::Err($crate::audit!($($arg)+))
+    };
+}
+
+/// Returns early with an [Audit](crate::audit::Audit) if the condition is false.
+///
+/// Without a message the audit will contain the condition that failed.
+///
+/// # Examples
+///
+/// ```
+/// use anyways::ensure;
+///
+/// fn load(size: usize) -> anyways::Result<()> {
+///     ensure!(size > 0);
+///     ensure!(size < 1024, "The plugin is {size} bytes which is too big");
+///     Ok(())
+/// }
+///
+/// assert!(load(0).is_err());
+/// assert!(load(2048).is_err());
+/// ```
+#[macro_export]
+macro_rules! ensure {
+    ($cond:expr $(,)?) => {
+        if !$cond {
+            $crate::bail!(::std::concat!("Condition failed: `", ::std::stringify!($cond), "`"))
+        }
+    };
+    ($cond:expr, $($arg:tt)+) => {
+        if !$cond {
+            $crate::bail!($($arg)+)
+        }
+    };
+}
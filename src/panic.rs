@@ -0,0 +1,55 @@
+//! Reports panics through the audit pipeline, see [install_panic_hook](crate::install_panic_hook).
+
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::panic::PanicHookInfo;
+use std::thread;
+
+use crate::audit::{Audit, AuditError, AuditLocation, Err};
+
+/// The error an audit of a panic holds, it can be used to recognize panics in the processor or formatter.
+#[derive(Debug)]
+pub struct PanicError {
+    /// The name of the thread that panicked.
+    pub thread: Option<String>,
+    pub message: String,
+}
+
+impl Display for PanicError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "thread '{}' panicked: {}",
+            self.thread.as_deref().unwrap_or("<unnamed>"),
+            self.message
+        )
+    }
+}
+
+impl Error for PanicError {}
+
+/// Creates the audit of a panic, the backtrace is captured in the panic hook.
+pub(crate) fn audit(info: &PanicHookInfo) -> Audit {
+    let payload = info.payload();
+    let message = payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "Box<dyn Any>".to_string());
+
+    let error: Box<dyn Err> = Box::new(PanicError {
+        thread: thread::current().name().map(str::to_string),
+        message,
+    });
+    let mut error = AuditError::from(error);
+    error.location = info.location().map(|location| AuditLocation {
+        file: Cow::Owned(location.file().to_string()),
+        line: location.line(),
+        column: location.column(),
+    });
+
+    let mut audit = Audit::new_empty();
+    audit.push_err(error);
+    audit
+}
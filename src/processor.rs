@@ -1,24 +1,43 @@
 use std::collections::{HashMap, HashSet};
-use std::hash::{Hash, Hasher};
 use std::mem::swap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use backtrace::{resolve_frame, BacktraceSymbol, Symbol};
-use owo_colors::{AnsiColors, DynColors, OwoColorize, Style};
+use backtrace::BacktraceSymbol;
+use owo_colors::{AnsiColors, DynColors};
 
 use entry::{ProcessingEntry, ProcessingValueMatcher};
 use file::ReporterFile;
+use snippet::{read_source, snippet_entries};
 
-use crate::audit::{Audit, AuditSection, AuditSectionEntry};
+use crate::audit::{Audit, AuditAttachmentKind, AuditLocation, AuditSection, AuditSectionEntry, AuditSectionKind};
+use crate::style::{SpanStyle, StyledText};
 
 pub mod entry;
 pub mod file;
+pub mod snippet;
 
 pub(crate) const UNKNOWN: fn() -> String = || "???".to_string();
 
-pub trait AuditProcessor: Sync {
+pub trait AuditProcessor: Send + Sync {
     /// This processes the audit which later gets formatted by an AuditReporter
     fn process(&self, audit: &Audit) -> Vec<AuditSection>;
+
+    /// Returns only the [Custom](AuditSectionKind::Custom) sections of [process](AuditProcessor::process),
+    /// for machine readable output like [Audit::to_json] which describes the errors and backtraces itself.
+    /// Processors should override this when they can skip resolving the backtraces.
+    fn custom_sections(&self, audit: &Audit) -> Vec<AuditSection> {
+        self.process(audit)
+            .into_iter()
+            .filter(|section| section.kind == AuditSectionKind::Custom)
+            .collect()
+    }
+
+    /// Processes the backtrace of a single audit of the tree into frames for machine readable output like [Audit::to_json].
+    /// Processors which only describe the backtrace through sections return no frames.
+    fn frames(&self, node: &AuditNode) -> Vec<ProcessedFrame> {
+        let _ = node;
+        Vec::new()
+    }
 }
 
 pub struct AnywaysAuditProcessorBuilder {
@@ -32,7 +51,15 @@ pub struct AnywaysAuditProcessorBuilder {
 
     pub file_remove_library_prefix: bool,
     pub file_shorten_current_dir: bool,
-    pub replace_style: Style,
+
+    pub error_sources: bool,
+
+    pub snippet_error_frames: bool,
+    pub snippet_all_frames: bool,
+    pub snippet_context_lines: usize,
+
+    pub trim_start: bool,
+    pub trim_end: bool,
 }
 
 impl Default for AnywaysAuditProcessorBuilder {
@@ -46,12 +73,19 @@ impl Default for AnywaysAuditProcessorBuilder {
             collapse_closure: true,
             file_remove_library_prefix: true,
             file_shorten_current_dir: true,
-            replace_style: Style::new().cyan()
+            error_sources: true,
+            snippet_error_frames: true,
+            snippet_all_frames: false,
+            snippet_context_lines: 2,
+            trim_start: true,
+            trim_end: true,
         }
     }
 }
 
 impl AnywaysAuditProcessorBuilder {
+    // Matchers are hashed by their pattern, which the cache inside of a regex does not change.
+    #[allow(clippy::mutable_key_type)]
     pub fn build(self) -> AnywaysAuditProcessor {
         let filter = HashSet::new();
         let mut replace = Vec::new();
@@ -130,9 +164,14 @@ impl AnywaysAuditProcessorBuilder {
             filter,
             replace,
             collapse,
-            replace_style: self.replace_style,
             file_remove_library_prefix: self.file_remove_library_prefix,
             file_shorten_current_dir: self.file_shorten_current_dir,
+            error_sources: self.error_sources,
+            snippet_error_frames: self.snippet_error_frames,
+            snippet_all_frames: self.snippet_all_frames,
+            snippet_context_lines: self.snippet_context_lines,
+            trim_start: self.trim_start,
+            trim_end: self.trim_end,
         }
     }
 }
@@ -141,10 +180,8 @@ pub struct AnywaysAuditProcessor {
     /// If a filter gets matched the entry will get removed
     pub filter: HashSet<ProcessingValueMatcher>,
 
-    /// If a shorthand get matched the entry will get replaced with the right side and colored on its default.
-    /// You can locally overwrite this by just setting a color.
+    /// If a shorthand get matched the entry will get replaced with the right side and styled as [SpanStyle::Replaced].
     pub replace: Vec<(ProcessingValueMatcher, String)>,
-    pub replace_style: Style,
 
     /// If a collapse gets matched the entry will be allowed to move outside of its file and inline its usage.
     pub collapse: HashSet<ProcessingValueMatcher>,
@@ -153,77 +190,340 @@ pub struct AnywaysAuditProcessor {
     pub file_remove_library_prefix: bool,
     /// If the source file is bound to the current directory it will get shortened to ./
     pub file_shorten_current_dir: bool,
+
+    /// Lists the errors exposed through [Error::source](std::error::Error::source) under every audit error.
+    pub error_sources: bool,
+
+    /// Shows the source lines around the frames where an error was added, if the file is part of the current project.
+    pub snippet_error_frames: bool,
+    /// Shows the source lines around every frame in the current project.
+    pub snippet_all_frames: bool,
+    /// The amount of lines shown above and below the line of a frame.
+    pub snippet_context_lines: usize,
+
+    /// Hides the frames of capturing the backtrace and creating the audit, which come before the first frame outside of anyways.
+    /// Panics also hide the panic machinery of std, like the short backtraces of std.
+    pub trim_start: bool,
+    /// Hides the frames of the runtime, which come after `__rust_begin_short_backtrace` like in the short backtraces of std.
+    pub trim_end: bool,
 }
 
 impl AuditProcessor for AnywaysAuditProcessor {
     fn process(&self, audit: &Audit) -> Vec<AuditSection> {
-        let mut sections = audit.custom_sections.clone();
+        let nodes = AuditNode::collect(audit);
+        let mut sections: Vec<AuditSection> = nodes
+            .iter()
+            .flat_map(|node| node.audit.custom_sections.iter().cloned())
+            .collect();
+
+        sections.push(self.create_error_section(audit));
+        sections.extend(self.create_attachment_sections(&nodes));
+        for node in &nodes {
+            if node.holds_only_children() {
+                continue;
+            }
 
-        let (section, errors) = self.create_error_section(audit);
-        sections.push(section);
-        sections.push(self.create_backtrace_section(audit, &errors));
+            let mut section = self.create_backtrace_section(node.audit, &self.error_locations(node));
+            if node.depth > 0 {
+                section.name = format!("{} {}", section.name, node.id);
+            }
+            sections.push(section);
+
+            #[cfg(feature = "tracing")]
+            sections.extend(self.node_span_trace_section(node));
+        }
+        sections
+    }
+
+    fn custom_sections(&self, audit: &Audit) -> Vec<AuditSection> {
+        let nodes = AuditNode::collect(audit);
+        let mut sections: Vec<AuditSection> = nodes
+            .iter()
+            .flat_map(|node| node.audit.custom_sections.iter().cloned())
+            .collect();
+
+        sections.extend(self.create_attachment_sections(&nodes));
+        #[cfg(feature = "tracing")]
+        for node in &nodes {
+            if node.holds_only_children() {
+                continue;
+            }
+            sections.extend(self.node_span_trace_section(node));
+        }
         sections
     }
+
+    fn frames(&self, node: &AuditNode) -> Vec<ProcessedFrame> {
+        self.process_backtrace(node.audit, &self.error_locations(node))
+            .0
+            .into_iter()
+            .flat_map(|file| {
+                let path = file.path;
+                file.entries.into_iter().map(move |entry| entry.frame(path.as_deref()))
+            })
+            .collect()
+    }
 }
 
 impl AnywaysAuditProcessor {
-    pub fn create_error_section(&self, audit: &Audit) -> (AuditSection, Errors) {
-        let mut errors = HashMap::new();
+    /// The documentation link of an error code, None if no url template is set.
+    fn code_url_entry(code: &str, depth: usize) -> Option<AuditSectionEntry> {
+        let url = crate::code::url(code)?;
+        Some(AuditSectionEntry {
+            prefix_left: Some(StyledText::new()),
+            text: StyledText::styled("see ", SpanStyle::Separator).with(url, SpanStyle::Link),
+            depth,
+            ..AuditSectionEntry::empty()
+        })
+    }
+
+    pub fn create_error_section(&self, audit: &Audit) -> AuditSection {
         let mut entries = Vec::new();
-        for (i, err) in audit.errors.iter().enumerate() {
-            // Try to resolve the location and append that to the errors lookup.
-            // This is later used to indicate where an error has occured in the backtrace.
-            if let Some(value) = &err.location {
-                resolve_frame(value, |symbol| {
-                    let location: ErrorLocationKey = symbol.into();
-                    if !errors.contains_key(&location) {
-                        errors.insert(location.clone(), Vec::new());
+        for node in AuditNode::collect(audit) {
+            // A joined audit without errors still shows its own code.
+            if let Some(code) = node.audit.code().filter(|_| node.audit.errors.is_empty()) {
+                entries.push(AuditSectionEntry {
+                    prefix_left: Some(StyledText::new()),
+                    text: StyledText::styled(format!("[{code}]"), SpanStyle::ErrorCode),
+                    depth: node.depth,
+                    ..AuditSectionEntry::empty()
+                });
+                entries.extend(Self::code_url_entry(code, node.depth + 1));
+            }
+
+            for (i, err) in node.audit.errors.iter().enumerate() {
+                let mut text = StyledText::new();
+                let code = node.audit.error_code(i);
+                if let Some(code) = code {
+                    text.push(format!("[{code}]"), SpanStyle::ErrorCode).push(" ", SpanStyle::Plain);
+                }
+                text.push(err.error.to_string(), SpanStyle::Plain);
+
+                // Push the section entry.
+                entries.push(AuditSectionEntry {
+                    prefix: None,
+                    prefix_left: Some(StyledText::styled(node.label(i), SpanStyle::ErrorLabel)),
+                    separator: StyledText::styled(
+                        if i != node.audit.errors.len() - 1 {
+                            "↓"
+                        } else {
+                            "→"
+                        },
+                        SpanStyle::Separator,
+                    ),
+                    prefix_right: None,
+                    text,
+                    suffix: None,
+                    depth: node.depth,
+                });
+
+                entries.extend(code.and_then(|code| Self::code_url_entry(code, node.depth + 1)));
+
+                if self.error_sources {
+                    let mut source = err.error.source();
+                    while let Some(err) = source {
+                        entries.push(AuditSectionEntry {
+                            prefix_left: Some(StyledText::new()),
+                            text: StyledText::styled("↳ ", SpanStyle::Separator)
+                                .with(err.to_string(), SpanStyle::Plain),
+                            depth: node.depth + 1,
+                            ..AuditSectionEntry::empty()
+                        });
+                        source = err.source();
                     }
-                    errors.get_mut(&location).unwrap().push(i);
+                }
+            }
+        }
+
+        AuditSection {
+            name: "Errors".to_string(),
+            color: DynColors::Ansi(AnsiColors::Red),
+            kind: AuditSectionKind::Errors,
+            entries,
+            footer: None,
+        }
+    }
+
+    /// Gathers the attachments of every audit into the Help, Note and Warning sections, empty sections are left out.
+    pub fn create_attachment_sections(&self, nodes: &[AuditNode]) -> Vec<AuditSection> {
+        let groups = [
+            ("Help", AnsiColors::Green, &[AuditAttachmentKind::Help, AuditAttachmentKind::Suggestion][..]),
+            ("Note", AnsiColors::Blue, &[AuditAttachmentKind::Note][..]),
+            ("Warning", AnsiColors::Yellow, &[AuditAttachmentKind::Warning][..]),
+        ];
+
+        let mut sections = Vec::new();
+        for (name, color, kinds) in groups {
+            let mut entries = Vec::new();
+            for node in nodes {
+                for attachment in node.audit.attachments().iter().filter(|v| kinds.contains(&v.kind)) {
+                    let text = match attachment.kind {
+                        AuditAttachmentKind::Suggestion => StyledText::styled("try ", SpanStyle::Separator)
+                            .with(attachment.message.as_str(), SpanStyle::Code),
+                        _ => attachment.message.as_str().into(),
+                    };
+                    entries.push(AuditSectionEntry {
+                        prefix_left: (node.depth > 0).then(|| {
+                            StyledText::styled(&node.id, SpanStyle::ErrorLabel)
+                        }),
+                        text,
+                        depth: node.depth,
+                        ..AuditSectionEntry::empty()
+                    });
+                }
+            }
+
+            if !entries.is_empty() {
+                sections.push(AuditSection {
+                    name: name.to_string(),
+                    color: DynColors::Ansi(color),
+                    kind: AuditSectionKind::Custom,
+                    entries,
+                    footer: None,
                 });
             }
+        }
+        sections
+    }
 
-            // Push the section entry.
-            entries.push(AuditSectionEntry {
-                prefix: None,
-                prefix_left: Some(format!("E{i}").red().to_string()),
-                separator: if i != audit.errors.len() - 1 {
-                    "↓"
-                } else {
-                    "→"
-                }.white().to_string(),
-                prefix_right: None,
-                text: format!("{}", err.error),
-                suffix: None,
-            });
+    /// Creates the lookup of error locations which is used to indicate where an error has occured in the backtrace.
+    pub fn error_locations(&self, node: &AuditNode) -> Errors {
+        let mut errors = HashMap::new();
+        for (i, err) in node.audit.errors.iter().enumerate() {
+            if let Some(location) = &err.location {
+                errors
+                    .entry(ErrorLocationKey::from(location))
+                    .or_insert_with(Vec::new)
+                    .push(node.label(i));
+            }
         }
 
-        (
-            AuditSection {
-                name: "Errors".to_string(),
-                color: DynColors::Ansi(AnsiColors::Red),
-                entries,
-            },
-            errors,
-        )
+        errors
     }
 
     pub fn create_backtrace_section(&self, audit: &Audit, errors: &Errors) -> AuditSection {
+        let mut entries = Vec::new();
+        let (files, hidden) = self.process_backtrace(audit, errors);
+        for file in files {
+            // Only files in the current project get snippets, the file is read once the first frame needs it.
+            let mut source = None;
+            let readable = !file.external && (self.snippet_error_frames || self.snippet_all_frames);
+
+            // File name
+            entries.push(AuditSectionEntry::text(StyledText::styled(
+                file.path
+                    .as_ref()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .unwrap_or_else(UNKNOWN),
+                if file.external { SpanStyle::ExternalFile } else { SpanStyle::File },
+            )));
+
+            // File entries
+            for entry in file.entries {
+                let snippet = readable && (self.snippet_all_frames || !entry.errors.is_empty());
+                let (line, column) = (entry.line, entry.character);
+                // The path of the file may have been shortened, so the path from the debug info is read instead.
+                let path = entry.file.clone();
+                entries.push(entry.build());
+
+                if let (true, Some(path), Some(line)) = (snippet, path, line) {
+                    let source = source.get_or_insert_with(|| read_source(&path));
+                    if let Some(source) = source {
+                        entries.extend(snippet_entries(source, line, column, self.snippet_context_lines));
+                    }
+                }
+            }
+
+            // Just a spacer after the files
+            entries.push(AuditSectionEntry::empty());
+        }
+        // pop last empty line ^
+        entries.pop();
+
+        AuditSection {
+            name: "Backtrace".to_string(),
+            color: DynColors::Ansi(AnsiColors::Yellow),
+            kind: AuditSectionKind::Backtrace,
+            entries,
+            footer: match hidden {
+                0 => None,
+                1 => Some("1 frame hidden".to_string()),
+                hidden => Some(format!("{hidden} frames hidden")),
+            },
+        }
+    }
+
+    /// Creates the span trace section of an audit in the tree, the sections of children are named after their label.
+    #[cfg(feature = "tracing")]
+    fn node_span_trace_section(&self, node: &AuditNode) -> Option<AuditSection> {
+        let mut section = self.create_span_trace_section(node.audit)?;
+        if node.depth > 0 {
+            section.name = format!("{} {}", section.name, node.id);
+        }
+        Some(section)
+    }
+
+    /// Lists the spans of the span trace from the innermost to the outermost span with their fields and source location.
+    /// Returns None if no spans were captured.
+    #[cfg(feature = "tracing")]
+    pub fn create_span_trace_section(&self, audit: &Audit) -> Option<AuditSection> {
+        let mut entries = Vec::new();
+        let mut i = 0;
+        audit.span_trace()?.with_spans(|metadata, fields| {
+            let mut text = StyledText::styled(metadata.name(), SpanStyle::Plain);
+            if !fields.is_empty() {
+                text.push(" ", SpanStyle::Plain).push(fields, SpanStyle::Code);
+            }
+            entries.push(AuditSectionEntry {
+                prefix_left: Some(StyledText::styled(i.to_string(), SpanStyle::Location)),
+                prefix_right: Some(StyledText::styled(metadata.target(), SpanStyle::Module)),
+                text,
+                ..AuditSectionEntry::empty()
+            });
+
+            if let (Some(file), Some(line)) = (metadata.file(), metadata.line()) {
+                entries.push(AuditSectionEntry {
+                    prefix_left: Some(StyledText::new()),
+                    text: StyledText::styled("at ", SpanStyle::Separator).with(format!("{file}:{line}"), SpanStyle::Location),
+                    depth: 1,
+                    ..AuditSectionEntry::empty()
+                });
+            }
+
+            i += 1;
+            true
+        });
+
+        (!entries.is_empty()).then(|| AuditSection {
+            name: "Span trace".to_string(),
+            color: DynColors::Ansi(AnsiColors::Cyan),
+            kind: AuditSectionKind::Custom,
+            entries,
+            footer: None,
+        })
+    }
+
+    /// Reads the backtrace and applies the trimming, filters, collapses and replacements on its entries.
+    /// Returns the files and the amount of frames which got hidden.
+    pub fn process_backtrace(&self, audit: &Audit, errors: &Errors) -> (Vec<ReporterFile>, usize) {
+        let (read, mut hidden) = self.read_backtrace(audit, errors);
+
         // Apply filter on entries.
         let mut files: Vec<ReporterFile> = Vec::new();
-        for mut file in self.read_backtrace(audit, errors) {
+        for mut file in read {
             let mut entries = Vec::new();
             'entry: for mut entry in file.entries {
                 // If any filter matches skip this entry
                 for matcher in &self.filter {
-                    if entry.value.matches(matcher) {
+                    if entry.matches(matcher) {
+                        hidden += 1;
                         continue 'entry;
                     }
                 }
 
                 // Check if this entry is collapsable
                 for matcher in &self.collapse {
-                    if entry.value.matches(matcher) {
+                    if entry.matches(matcher) {
                         entry.collapsable = true;
                         break;
                     }
@@ -231,9 +531,7 @@ impl AnywaysAuditProcessor {
 
                 // Replace everything you can.
                 for (matcher, to) in &self.replace {
-                    entry
-                        .value
-                        .replace(matcher, &(to.style(self.replace_style).to_string()));
+                    entry.replace(matcher, to);
                 }
 
                 entries.push(entry);
@@ -258,34 +556,11 @@ impl AnywaysAuditProcessor {
             files.push(file);
         }
 
-        let mut entries = Vec::new();
-        for file in files {
-            // File name
-            entries.push(AuditSectionEntry::text(
-                file.path
-                    .map(|p| p.to_str().unwrap().white().bold().to_string())
-                    .unwrap_or_else(UNKNOWN),
-            ));
-
-            // File entries
-            for entry in file.entries {
-                entries.push(entry.build());
-            }
-
-            // Just a spacer after the files
-            entries.push(AuditSectionEntry::empty());
-        }
-        // pop last empty line ^
-        entries.pop();
-
-        AuditSection {
-            name: "Backtrace".to_string(),
-            color: DynColors::Ansi(AnsiColors::Yellow),
-            entries,
-        }
+        (files, hidden)
     }
 
-    fn read_backtrace(&self, audit: &Audit, errors: &Errors) -> Vec<ReporterFile> {
+    /// Reads the resolved backtrace into files, returns the files and the amount of trimmed frames.
+    fn read_backtrace(&self, audit: &Audit, errors: &Errors) -> (Vec<ReporterFile>, usize) {
         let mut backtrace = audit.backtrace.clone();
         // Make sure that the backtrace is resolved.
         // We need to sadly clone the backtrace as we do not have a mutable reference in here to resolve the backtrace,
@@ -293,64 +568,186 @@ impl AnywaysAuditProcessor {
         // However Audits are mostly only reported once so ¯\_(ツ)_/¯
         backtrace.resolve();
 
+        let mut symbols: Vec<&BacktraceSymbol> = backtrace.frames().iter().flat_map(|frame| frame.symbols()).collect();
+        let total = symbols.len();
+
+        if self.trim_end {
+            let end = symbols.iter().position(|symbol| {
+                symbol
+                    .name()
+                    .is_some_and(|name| name.to_string().contains("__rust_begin_short_backtrace"))
+            });
+            if let Some(end) = end {
+                symbols.truncate(end);
+            }
+        }
+
+        if self.trim_start {
+            // Panics go through the panic machinery of std first, which std marks the end of.
+            let marker = symbols.iter().position(|symbol| {
+                symbol
+                    .name()
+                    .is_some_and(|name| name.to_string().contains("__rust_end_short_backtrace"))
+            });
+            if let Some(marker) = marker {
+                symbols.drain(..=marker);
+            }
+
+            let caller = symbols
+                .iter()
+                .position(|symbol| !symbol.name().is_some_and(|name| is_internal(&format!("{name:#}"))))
+                .unwrap_or(symbols.len());
+            symbols.drain(..caller);
+        }
+
         let mut files = Vec::new();
         let mut entries = Vec::new();
         let mut old_path = None;
 
-        for frame in backtrace.frames() {
-            for symbol in frame.symbols() {
-                let location: ErrorLocationKey = symbol.into();
-
-                if old_path != location.filename {
-                    let mut values = Vec::new();
-                    swap(&mut values, &mut entries);
-                    files.push(ReporterFile::new(old_path, values, self.file_remove_library_prefix, self.file_shorten_current_dir));
-                    old_path = location.filename.clone();
-                }
+        for symbol in &symbols {
+            let filename = symbol.filename().map(|m| m.to_owned());
 
-                entries.push(ProcessingEntry::new(symbol, errors));
+            if old_path != filename {
+                let mut values = Vec::new();
+                swap(&mut values, &mut entries);
+                files.push(ReporterFile::new(old_path, values, self.file_remove_library_prefix, self.file_shorten_current_dir));
+                old_path = filename;
             }
+
+            entries.push(ProcessingEntry::new(symbol, errors));
         }
+        files.push(ReporterFile::new(old_path, entries, self.file_remove_library_prefix, self.file_shorten_current_dir));
 
-        files
+        (files, total - symbols.len())
     }
 }
 
-pub type Errors = HashMap<ErrorLocationKey, Vec<usize>>;
+/// The crates whose frames capture the backtrace and create the audit.
+const INTERNAL_CRATES: [&str; 2] = ["backtrace", "anyways"];
 
-#[derive(Clone)]
-pub struct ErrorLocationKey {
-    pub name: Option<Vec<u8>>,
-    pub filename: Option<PathBuf>,
+/// The traits which `?` goes through to convert an error into an audit.
+const CONVERSION_TRAITS: [&str; 3] = ["core::ops::try_trait::", "core::convert::From<", "core::convert::Into<"];
+
+/// Checks if the demangled symbol name belongs to one of the [INTERNAL_CRATES] or implements one of the [CONVERSION_TRAITS].
+/// Trait implementations like `<Result<T, E> as anyways::ext::AuditExt<T>>::wrap_err` belong to both the type and the trait.
+fn is_internal(name: &str) -> bool {
+    let paths = match name.strip_prefix('<') {
+        Some(implementation) => match implementation.split_once(" as ") {
+            Some((ty, tr)) => {
+                if CONVERSION_TRAITS.iter().any(|conversion| tr.starts_with(conversion)) {
+                    return true;
+                }
+                vec![ty, tr]
+            }
+            None => vec![implementation],
+        },
+        None => vec![name],
+    };
+
+    paths.iter().any(|path| {
+        INTERNAL_CRATES.iter().any(|krate| {
+            path.strip_prefix(krate)
+                .and_then(|rest| rest.strip_prefix("::"))
+                // The tests of anyways are not part of the library.
+                .is_some_and(|rest| !rest.starts_with("tests::"))
+        })
+    })
 }
 
-impl Eq for ErrorLocationKey {}
-impl PartialEq for ErrorLocationKey {
-    fn eq(&self, other: &Self) -> bool {
-        self.name.eq(&other.name) && self.filename.eq(&other.filename)
-    }
+/// An audit in the tree formed by an audit and its children.
+pub struct AuditNode<'a> {
+    pub audit: &'a Audit,
+    /// The label of the audit, `E` for the root and `E0.1`, `E0.2` for its children.
+    /// The children of a child are numbered after its errors,
+    /// so the first child of `E0.2` with the errors `E0.2` and `E0.2.1` is `E0.2.2`.
+    pub id: String,
+    pub depth: usize,
 }
 
-impl Hash for ErrorLocationKey {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.name.hash(state);
-        self.filename.hash(state);
+impl<'a> AuditNode<'a> {
+    /// Collects the audit and all of its children depth first.
+    pub fn collect(audit: &'a Audit) -> Vec<AuditNode<'a>> {
+        fn walk<'a>(audit: &'a Audit, id: &str, depth: usize, nodes: &mut Vec<AuditNode<'a>>) {
+            // The errors of the root are listed next to each other, so its children all branch off `E0`.
+            let (parent, start) = match depth {
+                0 => ("E0", 1),
+                _ => (id, audit.errors.len().max(1)),
+            };
+            for (i, child) in audit.children().iter().enumerate() {
+                let id = format!("{parent}.{}", start + i);
+                nodes.push(AuditNode {
+                    audit: child,
+                    id: id.clone(),
+                    depth: depth + 1,
+                });
+                walk(child, &id, depth + 1, nodes);
+            }
+        }
+
+        let mut nodes = vec![AuditNode {
+            audit,
+            id: "E".to_string(),
+            depth: 0,
+        }];
+        walk(audit, "E", 0, &mut nodes);
+        nodes
     }
-}
 
-impl From<&Symbol> for ErrorLocationKey {
-    fn from(symbol: &Symbol) -> Self {
-        ErrorLocationKey {
-            name: symbol.name().map(|m| m.as_bytes().to_vec()),
-            filename: symbol.filename().map(|m| m.to_owned()),
+    /// Returns the label of an error in this audit, for example `E1`, `E0.2` or `E0.2.1`.
+    pub fn label(&self, error: usize) -> String {
+        match (self.depth, error) {
+            (0, _) => format!("{}{error}", self.id),
+            (_, 0) => self.id.clone(),
+            _ => format!("{}.{error}", self.id),
         }
     }
+
+    /// Checks if this is a joined audit without errors of its own, which only holds its children and has no backtrace to show.
+    pub fn holds_only_children(&self) -> bool {
+        self.audit.errors.is_empty() && !self.audit.children().is_empty()
+    }
 }
-impl From<&BacktraceSymbol> for ErrorLocationKey {
-    fn from(symbol: &BacktraceSymbol) -> Self {
+
+/// A backtrace frame after the processor filtered and shortened it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProcessedFrame {
+    /// The file the frame is listed under, collapsed frames are listed under the file before them.
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    pub module: Option<String>,
+    pub function: String,
+    /// The labels of the errors which were added at this frame.
+    pub errors: Vec<String>,
+}
+
+/// The labels of the errors at each location.
+pub type Errors = HashMap<ErrorLocationKey, Vec<String>>;
+
+/// The file and line of an error location, frames on the same line are marked with the error.
+#[derive(Clone, Eq, PartialEq, Hash)]
+pub struct ErrorLocationKey {
+    pub file: PathBuf,
+    pub line: u32,
+}
+
+impl ErrorLocationKey {
+    /// Checks if the symbol is at this location.
+    /// Caller locations are relative to the workspace while debug info paths are usually absolute,
+    /// so the symbol path only needs to end with the location path.
+    pub fn matches(&self, symbol: &BacktraceSymbol) -> bool {
+        symbol.lineno() == Some(self.line)
+            && symbol
+                .filename()
+                .is_some_and(|filename| filename.ends_with(&self.file))
+    }
+}
+
+impl From<&AuditLocation> for ErrorLocationKey {
+    fn from(location: &AuditLocation) -> Self {
         ErrorLocationKey {
-            name: symbol.name().map(|m| m.as_bytes().to_vec()),
-            filename: symbol.filename().map(|m| m.to_owned()),
+            file: Path::new(location.file.as_ref()).to_path_buf(),
+            line: location.line,
         }
     }
 }
@@ -1,18 +1,53 @@
 use crate::audit::AuditSectionEntry;
-use crate::processor::{Errors, UNKNOWN};
+use crate::processor::{Errors, ProcessedFrame, UNKNOWN};
+use std::fmt::{Debug, Formatter};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use crate::style::{SpanStyle, StyledText};
 use backtrace::BacktraceSymbol;
-use owo_colors::{OwoColorize};
+
+// Replaced text is wrapped in these private use characters so it can still be styled after every replacement has been applied.
+const REPLACED_START: char = '\u{E000}';
+const REPLACED_END: char = '\u{E001}';
 
 pub struct ProcessingEntry {
+    /// The source file of the frame as it was read from the debug info.
+    pub file: Option<PathBuf>,
     pub line: Option<u32>,
     pub character: Option<u32>,
     pub value: ProcessingValue,
-    pub errors: Option<String>,
+    /// The labels of the errors which were added at this entry.
+    pub errors: Vec<String>,
 
     pub collapsable: bool,
 }
 
 impl ProcessingEntry {
+    /// Checks if the matcher matches this entry, unlike [ProcessingValue::matches] this also checks the file and custom matchers.
+    pub fn matches(&self, matcher: &ProcessingValueMatcher) -> bool {
+        match matcher {
+            ProcessingValueMatcher::File(pattern) => self
+                .file
+                .as_ref()
+                .is_some_and(|file| glob_matches(pattern, &file.to_string_lossy())),
+            ProcessingValueMatcher::Custom(predicate) => predicate(self),
+            matcher => self.value.matches(matcher),
+        }
+    }
+
+    /// Replaces the matched part of the value, file and custom matchers replace the whole value of a matching entry.
+    pub fn replace(&mut self, matcher: &ProcessingValueMatcher, to: &str) {
+        match matcher {
+            ProcessingValueMatcher::File(_) | ProcessingValueMatcher::Custom(_) => {
+                if self.matches(matcher) {
+                    self.value.replace_value(to);
+                }
+            }
+            matcher => self.value.replace(matcher, to),
+        }
+    }
+
     pub fn get_location(&self) -> String {
         format!(
             "{}:{}",
@@ -21,34 +56,80 @@ impl ProcessingEntry {
                 .map(|v| v.to_string())
                 .unwrap_or_else(UNKNOWN)
         )
-        .blue()
-        .to_string()
     }
 
-    pub fn build(self) -> AuditSectionEntry {
-        let value: String = match &self.value {
-            ProcessingValue::Entry { value, .. } => value.to_string(),
+    pub fn frame(self, file: Option<&Path>) -> ProcessedFrame {
+        ProcessedFrame {
+            file: file.map(|p| p.to_string_lossy().to_string()),
+            line: self.line,
+            column: self.character,
+            module: self.value.get_module().map(|v| styled_value(v, SpanStyle::Module).to_string()),
+            function: self.text().to_string(),
+            errors: self.errors,
+        }
+    }
+
+    /// The function of the entry, casts are written as `from -> value`.
+    pub fn text(&self) -> StyledText {
+        let mut text = StyledText::new();
+        match &self.value {
+            ProcessingValue::Entry { value, .. } => {
+                text.append(styled_value(value, SpanStyle::Plain));
+            }
             ProcessingValue::Cast { from, value, .. } => {
-                format!("{from} {} {value}", "->".white())
+                text.append(styled_value(from, SpanStyle::Plain));
+                text.push(" -> ", SpanStyle::Separator);
+                text.append(styled_value(value, SpanStyle::Plain));
+            }
+            ProcessingValue::Unknown => {
+                text.push("???", SpanStyle::Plain);
             }
-            ProcessingValue::Unknown => "???".to_string(),
         };
+        text
+    }
+
+    pub fn build(self) -> AuditSectionEntry {
+        let text = self.text();
+
+        let mut prefix = StyledText::new();
+        for (i, label) in self.errors.iter().enumerate() {
+            if i != 0 {
+                prefix.push(" ", SpanStyle::Plain);
+            }
+            prefix.push(label.as_str(), SpanStyle::ErrorLabel);
+        }
 
         AuditSectionEntry {
-            prefix_left: Some(self.get_location()),
-            separator: if self.errors.is_some() {
-                "+".red().bold().to_string()
+            prefix_left: Some(StyledText::styled(self.get_location(), SpanStyle::Location)),
+            separator: if self.errors.is_empty() {
+                StyledText::styled("|", SpanStyle::Separator)
             } else {
-                "|".to_string()
+                StyledText::styled("+", SpanStyle::ErrorMarker)
             },
-            prefix: self.errors,
-            prefix_right: self.value.get_module().map(|v| v.purple().to_string()),
-            text: value,
+            prefix: (!prefix.is_empty()).then_some(prefix),
+            prefix_right: self.value.get_module().map(|v| styled_value(v, SpanStyle::Module)),
+            text,
             suffix: None,
+            depth: 0,
         }
     }
 }
 
+/// Splits the text replaced by [ProcessingValue::replace] into [SpanStyle::Replaced] spans.
+fn styled_value(value: &str, style: SpanStyle) -> StyledText {
+    let mut text = StyledText::new();
+    let mut rest = value;
+    while let Some((before, after)) = rest.split_once(REPLACED_START) {
+        text.push(before, style);
+        let (replaced, after) = after.split_once(REPLACED_END).unwrap_or((after, ""));
+        text.push(replaced, SpanStyle::Replaced);
+        rest = after;
+    }
+
+    text.push(rest, style);
+    text
+}
+
 #[derive(Debug)]
 pub enum ProcessingValue {
     Entry {
@@ -65,12 +146,148 @@ pub enum ProcessingValue {
     Unknown,
 }
 
-#[derive(Ord, PartialOrd, Eq, PartialEq, Clone, Hash, Debug)]
+/// Matches entries for the filters, replacements and collapses of the [AnywaysAuditProcessor](crate::processor::AnywaysAuditProcessor).
+#[derive(Clone)]
 pub enum ProcessingValueMatcher {
+    /// Matches the value without its module exactly, for example `ops::function::FnOnce::call_once`.
     Value(String),
+    /// Matches the module exactly, for example `core`.
     Module(String),
+    /// Matches the start of the value without its module.
     Item(String),
+    /// Matches a single `::` separated segment of the value.
     Path(String),
+    /// Matches the whole path including the module with a glob, for example `tokio::runtime::*`.
+    /// `*` matches any text and `?` matches a single character. Casts match if either of their paths match.
+    /// Replacing replaces every matching path including its module.
+    Glob(String),
+    /// Matches the whole path including the module with a regex, casts match if either of their paths match.
+    /// Replacing replaces the matches inside of the paths including their module, the replacement can refer to groups like `$1`.
+    #[cfg(feature = "regex")]
+    Regex(regex::Regex),
+    /// Matches the source file of the entry with a glob, for example `*/.cargo/registry/*`.
+    /// Only the entry can be matched against a file, see [ProcessingEntry::matches].
+    /// Replacing replaces the value of a matching entry and keeps its module.
+    File(String),
+    /// Matches the entries the function returns true for, see [ProcessingEntry::matches].
+    /// Replacing replaces the value of a matching entry and keeps its module.
+    Custom(Arc<dyn Fn(&ProcessingEntry) -> bool + Send + Sync>),
+}
+
+impl ProcessingValueMatcher {
+    /// Creates a custom matcher from the function.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anyways::processor::AnywaysAuditProcessorBuilder;
+    /// use anyways::processor::entry::ProcessingValueMatcher;
+    ///
+    /// let mut processor = AnywaysAuditProcessorBuilder::default().build();
+    /// // Hide every frame from crates we do not own.
+    /// processor.filter.insert(ProcessingValueMatcher::custom(|entry| {
+    ///     entry.value.get_module().is_some_and(|module| !matches!(module, "my_app" | "my_lib"))
+    /// }));
+    /// ```
+    pub fn custom(predicate: impl Fn(&ProcessingEntry) -> bool + Send + Sync + 'static) -> ProcessingValueMatcher {
+        ProcessingValueMatcher::Custom(Arc::new(predicate))
+    }
+
+    /// The position of the variant, matchers are compared by their variant first.
+    fn rank(&self) -> u8 {
+        match self {
+            ProcessingValueMatcher::Value(_) => 0,
+            ProcessingValueMatcher::Module(_) => 1,
+            ProcessingValueMatcher::Item(_) => 2,
+            ProcessingValueMatcher::Path(_) => 3,
+            ProcessingValueMatcher::Glob(_) => 4,
+            #[cfg(feature = "regex")]
+            ProcessingValueMatcher::Regex(_) => 5,
+            ProcessingValueMatcher::File(_) => 6,
+            ProcessingValueMatcher::Custom(_) => 7,
+        }
+    }
+
+    /// The text of the matcher, custom matchers are identified by their function instead.
+    fn pattern(&self) -> Option<&str> {
+        match self {
+            ProcessingValueMatcher::Value(v)
+            | ProcessingValueMatcher::Module(v)
+            | ProcessingValueMatcher::Item(v)
+            | ProcessingValueMatcher::Path(v)
+            | ProcessingValueMatcher::Glob(v)
+            | ProcessingValueMatcher::File(v) => Some(v),
+            #[cfg(feature = "regex")]
+            ProcessingValueMatcher::Regex(regex) => Some(regex.as_str()),
+            ProcessingValueMatcher::Custom(_) => None,
+        }
+    }
+}
+
+// Matchers are equal if their variant and pattern are, custom matchers only if they share the same function.
+impl PartialEq for ProcessingValueMatcher {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (ProcessingValueMatcher::Custom(a), ProcessingValueMatcher::Custom(b)) => Arc::ptr_eq(a, b),
+            _ => (self.rank(), self.pattern()) == (other.rank(), other.pattern()),
+        }
+    }
+}
+
+impl Eq for ProcessingValueMatcher {}
+
+impl Hash for ProcessingValueMatcher {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self.rank(), self.pattern()).hash(state);
+    }
+}
+
+impl Debug for ProcessingValueMatcher {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProcessingValueMatcher::Value(v) => f.debug_tuple("Value").field(v).finish(),
+            ProcessingValueMatcher::Module(v) => f.debug_tuple("Module").field(v).finish(),
+            ProcessingValueMatcher::Item(v) => f.debug_tuple("Item").field(v).finish(),
+            ProcessingValueMatcher::Path(v) => f.debug_tuple("Path").field(v).finish(),
+            ProcessingValueMatcher::Glob(v) => f.debug_tuple("Glob").field(v).finish(),
+            #[cfg(feature = "regex")]
+            ProcessingValueMatcher::Regex(v) => f.debug_tuple("Regex").field(&v.as_str()).finish(),
+            ProcessingValueMatcher::File(v) => f.debug_tuple("File").field(v).finish(),
+            ProcessingValueMatcher::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+/// Matches the text with a glob where `*` matches any text and `?` matches a single character.
+pub fn glob_matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    // The position after the last star and the text position it is matched up to, to backtrack on a mismatch.
+    let (mut p, mut t) = (0, 0);
+    let mut star = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p + 1, t));
+                p += 1;
+            }
+            Some(ch) if *ch == '?' || *ch == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|ch| *ch == '*')
 }
 
 impl ProcessingValue {
@@ -96,6 +313,26 @@ impl ProcessingValue {
         }
     }
 
+    /// The full paths including the module, casts have the path they are cast from and the path they are cast to.
+    pub fn get_paths(&self) -> Vec<String> {
+        fn path(module: &Option<String>, value: &str) -> String {
+            match module {
+                Some(module) => format!("{module}::{value}"),
+                None => value.to_string(),
+            }
+        }
+
+        match self {
+            ProcessingValue::Entry { module, value } => vec![path(module, value)],
+            ProcessingValue::Cast { from_module, from, module, value } => {
+                vec![path(from_module, from), path(module, value)]
+            }
+            ProcessingValue::Unknown => Vec::new(),
+        }
+    }
+
+    /// Checks if the matcher matches the value, [File](ProcessingValueMatcher::File) and [Custom](ProcessingValueMatcher::Custom)
+    /// matchers need the whole entry and never match here.
     pub fn matches(&self, matcher: &ProcessingValueMatcher) -> bool {
         let value = match matcher {
             ProcessingValueMatcher::Value(target) => match self {
@@ -135,11 +372,61 @@ impl ProcessingValue {
                 }
                 ProcessingValue::Unknown => false,
             },
+            ProcessingValueMatcher::Glob(pattern) => {
+                self.get_paths().iter().any(|path| glob_matches(pattern, path))
+            }
+            #[cfg(feature = "regex")]
+            ProcessingValueMatcher::Regex(regex) => self.get_paths().iter().any(|path| regex.is_match(path)),
+            ProcessingValueMatcher::File(_) | ProcessingValueMatcher::Custom(_) => false,
         };
         value
     }
 
+    /// Rewrites the full paths including their module, a rewritten path keeps no separate module.
+    /// The function returns None to keep a path unchanged.
+    fn map_paths(&mut self, mut map: impl FnMut(&str) -> Option<String>) {
+        fn rewrite(module: &mut Option<String>, value: &mut String, map: &mut impl FnMut(&str) -> Option<String>) {
+            let path = match module {
+                Some(module) => format!("{module}::{value}"),
+                None => value.clone(),
+            };
+            if let Some(path) = map(&path) {
+                *module = None;
+                *value = path;
+            }
+        }
+
+        match self {
+            ProcessingValue::Entry { module, value } => rewrite(module, value, &mut map),
+            ProcessingValue::Cast { from_module, from, module, value } => {
+                rewrite(from_module, from, &mut map);
+                rewrite(module, value, &mut map);
+            }
+            ProcessingValue::Unknown => {}
+        }
+    }
+
+    /// Replaces the whole value, casts are replaced with a plain entry.
+    pub fn replace_value(&mut self, to: &str) {
+        self.set_value(format!("{REPLACED_START}{to}{REPLACED_END}"));
+    }
+
+    fn set_value(&mut self, to: String) {
+        match self {
+            ProcessingValue::Entry { value, .. } => *value = to,
+            ProcessingValue::Cast { module, .. } => {
+                *self = ProcessingValue::Entry {
+                    module: module.take(),
+                    value: to,
+                }
+            }
+            ProcessingValue::Unknown => {}
+        }
+    }
+
+    /// Replaces the matched part of the value, the replacement is later styled as [SpanStyle::Replaced].
     pub fn replace(&mut self, matcher: &ProcessingValueMatcher, to: &str) {
+        let to = &format!("{REPLACED_START}{to}{REPLACED_END}");
         match matcher {
             ProcessingValueMatcher::Value(from) => match self {
                 ProcessingValue::Cast { value, .. } | ProcessingValue::Entry { value, .. } => {
@@ -194,6 +481,14 @@ impl ProcessingValue {
                 }
                 ProcessingValue::Unknown => {}
             },
+            ProcessingValueMatcher::Glob(pattern) => {
+                self.map_paths(|path| glob_matches(pattern, path).then(|| to.clone()));
+            }
+            #[cfg(feature = "regex")]
+            ProcessingValueMatcher::Regex(regex) => {
+                self.map_paths(|path| regex.is_match(path).then(|| regex.replace_all(path, to.as_str()).into_owned()));
+            }
+            ProcessingValueMatcher::File(_) | ProcessingValueMatcher::Custom(_) => {}
         }
     }
 }
@@ -202,19 +497,18 @@ impl ProcessingEntry {
     pub fn new(symbol: &BacktraceSymbol, errors: &Errors) -> ProcessingEntry {
         let value = symbol
             .name()
-            .map(|v| Self::acquire_value(Self::strip_hash(&v.to_string())))
+            // The alternate form leaves out the hashes of both legacy and v0 mangled symbols.
+            .map(|v| Self::acquire_value(&format!("{v:#}")))
             .unwrap_or(ProcessingValue::Unknown);
 
-        let errors = errors.get(&symbol.into()).map(|err| {
-            let errors: Vec<String> = err
-                .iter()
-                .map(|id| format!("E{id}").red().to_string())
-                .collect();
-
-            errors.join(" ")
-        });
+        let errors = errors
+            .iter()
+            .filter(|(location, _)| location.matches(symbol))
+            .flat_map(|(_, labels)| labels.iter().cloned())
+            .collect();
 
         ProcessingEntry {
+            file: symbol.filename().map(Path::to_path_buf),
             line: symbol.lineno(),
             character: symbol.colno(),
             value,
@@ -223,14 +517,6 @@ impl ProcessingEntry {
         }
     }
 
-    fn strip_hash(value: &str) -> &str {
-        if let Some((out, _)) = value.rsplit_once("::") {
-            return out;
-        }
-
-        value
-    }
-
     fn acquire_value(value: &str) -> ProcessingValue {
         // this is for the as expression
         if value.starts_with('<') {
@@ -4,11 +4,19 @@ use crate::processor::entry::ProcessingEntry;
 
 pub struct ReporterFile {
 	pub path: Option<PathBuf>,
+	/// If the file lies outside of the current project, like the standard library or a dependency.
+	pub external: bool,
 	pub entries: Vec<ProcessingEntry>,
 }
 
 impl ReporterFile {
 	pub fn new(mut path: Option<PathBuf>, entries: Vec<ProcessingEntry>, file_remove_library_prefix: bool, file_shorten_current_dir: bool) -> ReporterFile {
+		// Relative paths come from the crates in the current workspace.
+		let external = match &path {
+			Some(path) => path.is_absolute() && !env::current_dir().is_ok_and(|dir| path.starts_with(dir)),
+			None => true,
+		};
+
 		if let Some(path) = &mut path {
 			if file_shorten_current_dir {
 				// If possible make absolute path relative
@@ -37,6 +45,6 @@ impl ReporterFile {
 			}
 		}
 
-		ReporterFile { path, entries }
+		ReporterFile { path, external, entries }
 	}
 }
@@ -0,0 +1,107 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::SystemTime;
+
+use crate::audit::AuditSectionEntry;
+use crate::style::{SpanStyle, StyledText};
+
+/// Reads the source file of a frame as it was recorded in the debug info.
+/// Relative paths are resolved against `CARGO_MANIFEST_DIR` as they are only valid from where the crate was built,
+/// and files which changed after the binary was built are skipped as their lines no longer match.
+pub fn read_source(path: &Path) -> Option<String> {
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        PathBuf::from(env::var_os("CARGO_MANIFEST_DIR")?).join(path)
+    };
+
+    static BUILT: OnceLock<Option<SystemTime>> = OnceLock::new();
+    let built = BUILT.get_or_init(|| fs::metadata(env::current_exe().ok()?).ok()?.modified().ok());
+    let modified = fs::metadata(&path).ok()?.modified().ok()?;
+    if built.is_some_and(|built| modified > built) {
+        return None;
+    }
+
+    fs::read_to_string(path).ok()
+}
+
+/// Creates the entries which show the source lines around the line of a frame.
+/// The text the column points at is highlighted and marked with a caret on the line below.
+pub fn snippet_entries(source: &str, line: u32, column: Option<u32>, context: usize) -> Vec<AuditSectionEntry> {
+    let Some(index) = (line as usize).checked_sub(1) else {
+        return Vec::new();
+    };
+
+    let raw: Vec<&str> = source.lines().collect();
+    let lines: Vec<String> = raw.iter().map(|line| line.replace('\t', "    ")).collect();
+    if index >= lines.len() {
+        return Vec::new();
+    }
+
+    let start = index.saturating_sub(context);
+    let end = (index + context + 1).min(lines.len());
+
+    // Remove the indentation all of the lines share.
+    let indent = lines[start..end]
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    let mut entries = Vec::new();
+    for (i, text) in lines.iter().enumerate().take(end).skip(start) {
+        let text = text.get(indent..).unwrap_or("").trim_end();
+        let mut entry = AuditSectionEntry {
+            prefix_left: Some(StyledText::styled((i + 1).to_string(), SpanStyle::Location)),
+            separator: StyledText::styled("|", SpanStyle::Separator),
+            ..AuditSectionEntry::empty()
+        };
+
+        if i != index {
+            entry.text = StyledText::styled(text, SpanStyle::Code);
+            entries.push(entry);
+            continue;
+        }
+
+        // Columns count the characters of the original line where tabs are a single character.
+        let column = column.map(|column| {
+            let width: usize = raw[i]
+                .chars()
+                .take((column as usize).saturating_sub(1))
+                .map(|ch| if ch == '\t' { 4 } else { 1 })
+                .sum();
+            width.saturating_sub(indent)
+        });
+        match column.filter(|column| *column < text.chars().count()) {
+            Some(column) => {
+                let (before, rest) = text.split_at(text.char_indices().nth(column).map(|(i, _)| i).unwrap_or(text.len()));
+                let length = rest
+                    .char_indices()
+                    .find(|(_, ch)| !(ch.is_alphanumeric() || *ch == '_'))
+                    .map(|(i, ch)| if i == 0 { ch.len_utf8() } else { i })
+                    .unwrap_or(rest.len());
+                let (highlight, after) = rest.split_at(length);
+
+                entry.text = StyledText::styled(before, SpanStyle::Code)
+                    .with(highlight, SpanStyle::Highlight)
+                    .with(after, SpanStyle::Code);
+                entries.push(entry);
+                entries.push(AuditSectionEntry {
+                    prefix_left: Some(StyledText::new()),
+                    text: StyledText::styled(" ".repeat(column), SpanStyle::Plain)
+                        .with("^".repeat(highlight.chars().count()), SpanStyle::Highlight),
+                    ..AuditSectionEntry::empty()
+                });
+            }
+            None => {
+                entry.text = StyledText::styled(text, SpanStyle::Code);
+                entries.push(entry);
+            }
+        }
+    }
+
+    entries
+}
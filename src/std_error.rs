@@ -0,0 +1,145 @@
+//! Passing audits through APIs which expect a standard error type.
+
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+#[cfg(all(feature = "send", feature = "sync"))]
+use std::io;
+
+use crate::audit::{Audit, AuditError, Err};
+
+/// An [Audit] as a standard error.
+///
+/// The error shows the outermost audit error, and [Error::source] goes through the rest of the audit errors.
+/// The sources of an audit error are only exposed after the innermost audit error.
+/// Converting it back through [Audit::from] or [into_audit](AuditStdError::into_audit) gives the full audit again.
+///
+/// # Examples
+///
+/// ```
+/// use std::error::Error;
+/// use anyways::audit::Audit;
+/// use anyways::ext::AuditExt;
+///
+/// let audit = Err::<(), _>(Audit::new("Disk full")).wrap_err("Failed to save").unwrap_err();
+/// let error = audit.into_boxed_error();
+/// assert_eq!(error.to_string(), "Failed to save");
+/// assert_eq!(error.source().unwrap().to_string(), "Disk full");
+///
+/// let audit = Audit::from(error);
+/// assert_eq!(audit.errors.len(), 2);
+/// ```
+pub struct AuditStdError {
+    /// The audit without its errors, they are held by the links.
+    audit: Audit,
+    head: Option<Box<Link>>,
+}
+
+/// An audit error whose source is the next audit error.
+struct Link {
+    error: AuditError,
+    next: Option<Box<Link>>,
+}
+
+impl AuditStdError {
+    pub fn new(mut audit: Audit) -> AuditStdError {
+        let mut head = None;
+        for error in audit.errors.drain(..).rev() {
+            head = Some(Box::new(Link { error, next: head }));
+        }
+
+        AuditStdError { audit, head }
+    }
+
+    /// Gives back the audit this error was created from.
+    pub fn into_audit(self) -> Audit {
+        let mut audit = self.audit;
+        let mut link = self.head;
+        while let Some(current) = link {
+            audit.errors.push(current.error);
+            link = current.next;
+        }
+        audit
+    }
+
+    /// Takes the AuditStdError out of the error, also if it was wrapped in an [io::Error](std::io::Error).
+    pub(crate) fn recover(error: Box<dyn Err>) -> Result<AuditStdError, Box<dyn Err>> {
+        if error.is::<AuditStdError>() {
+            let error: Box<dyn Error> = error;
+            return Ok(*error.downcast::<AuditStdError>().unwrap());
+        }
+
+        let wrapped = error
+            .downcast_ref::<std::io::Error>()
+            .and_then(|io| io.get_ref())
+            .is_some_and(|inner| inner.is::<AuditStdError>());
+        if wrapped {
+            let error: Box<dyn Error> = error;
+            let inner: Box<dyn Error> = error.downcast::<std::io::Error>().unwrap().into_inner().unwrap();
+            return Ok(*inner.downcast::<AuditStdError>().unwrap());
+        }
+
+        Err(error)
+    }
+}
+
+impl Display for AuditStdError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.head {
+            Some(link) => Display::fmt(link, f),
+            None => f.write_str(&self.audit.summary()),
+        }
+    }
+}
+
+impl Debug for AuditStdError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(self, f)?;
+        let mut source = self.source();
+        while let Some(error) = source {
+            write!(f, ": {error}")?;
+            source = error.source();
+        }
+        Ok(())
+    }
+}
+
+impl Error for AuditStdError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.head.as_ref()?.source()
+    }
+}
+
+impl Display for Link {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&*self.error.error, f)
+    }
+}
+
+impl Debug for Link {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&*self.error.error, f)
+    }
+}
+
+impl Error for Link {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.next {
+            Some(next) => Some(&**next),
+            None => self.error.error.source(),
+        }
+    }
+}
+
+impl Audit {
+    /// Converts the audit into a boxed standard error, see [AuditStdError].
+    pub fn into_boxed_error(self) -> Box<dyn Err> {
+        Box::new(AuditStdError::new(self))
+    }
+
+    /// Converts the audit into an [io::Error] of the kind [Other](io::ErrorKind::Other), see [AuditStdError].
+    /// An io::Error needs its error to be Send and Sync, so this requires the `send` and `sync` features.
+    #[cfg(all(feature = "send", feature = "sync"))]
+    pub fn into_io_error(self) -> io::Error {
+        io::Error::other(AuditStdError::new(self))
+    }
+}
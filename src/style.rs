@@ -0,0 +1,106 @@
+//! Renderer independent styled text.
+//!
+//! The processor describes what a piece of text means and every [AuditFormatter](crate::formatter::AuditFormatter)
+//! decides how that looks in its own output.
+
+use std::fmt::{Display, Formatter};
+
+/// The meaning of a span of text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SpanStyle {
+    Plain,
+    /// A line and column like `12:5`.
+    Location,
+    /// The crate or module a function lives in.
+    Module,
+    /// The label of an error like `E0`.
+    ErrorLabel,
+    /// Text that was shortened by a replace rule of the processor.
+    Replaced,
+    /// The path of a source file.
+    File,
+    /// The path of a source file outside of the current project, like the standard library or a dependency.
+    ExternalFile,
+    /// Arrows and separators between the columns.
+    Separator,
+    /// The separator of a frame where an error was added.
+    ErrorMarker,
+    /// A line of source code.
+    Code,
+    /// The part of a source line a frame points at.
+    Highlight,
+    /// A stable error code like `APP-0042`.
+    ErrorCode,
+    /// A link to documentation.
+    Link,
+}
+
+/// A piece of text with a single style.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Span {
+    pub text: String,
+    pub style: SpanStyle,
+}
+
+/// Text made out of styled spans.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StyledText {
+    pub spans: Vec<Span>,
+}
+
+impl StyledText {
+    pub fn new() -> StyledText {
+        StyledText { spans: Vec::new() }
+    }
+
+    pub fn styled(text: impl Into<String>, style: SpanStyle) -> StyledText {
+        let mut out = StyledText::new();
+        out.push(text, style);
+        out
+    }
+
+    /// Appends a span, empty text gets skipped.
+    pub fn push(&mut self, text: impl Into<String>, style: SpanStyle) -> &mut Self {
+        let text = text.into();
+        if !text.is_empty() {
+            self.spans.push(Span { text, style });
+        }
+        self
+    }
+
+    pub fn append(&mut self, text: StyledText) -> &mut Self {
+        self.spans.extend(text.spans);
+        self
+    }
+
+    pub fn with(mut self, text: impl Into<String>, style: SpanStyle) -> StyledText {
+        self.push(text, style);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+}
+
+impl From<String> for StyledText {
+    fn from(text: String) -> Self {
+        StyledText::styled(text, SpanStyle::Plain)
+    }
+}
+
+impl From<&str> for StyledText {
+    fn from(text: &str) -> Self {
+        StyledText::styled(text, SpanStyle::Plain)
+    }
+}
+
+/// Writes the text without any styling.
+impl Display for StyledText {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for span in &self.spans {
+            f.write_str(&span.text)?;
+        }
+        Ok(())
+    }
+}